@@ -1,5 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use std::rc::Rc;
+use std::sync::Arc;
 use yarrr::body::{HittableScene, Sphere};
 use yarrr::image::ColorRGB;
 use yarrr::linalg::Vector3D;
@@ -8,11 +8,11 @@ use yarrr::prelude::Scatter;
 use yarrr::ray::{HitRecord, Hittable, Ray};
 
 fn make_random_vector() -> Vector3D {
-    Vector3D::random(0.0, 1.0)
+    Vector3D::random(0.0, 1.0, &mut rand::thread_rng())
 }
 
 fn unit_sphere_sample() -> Vector3D {
-    Vector3D::unit_sphere_sample()
+    Vector3D::unit_sphere_sample(&mut rand::thread_rng())
 }
 
 fn hit_one_sphere() {
@@ -21,50 +21,64 @@ fn hit_one_sphere() {
     sphere.hit(&ray, 0.00001, f32::MAX);
 }
 
-fn hit_n_spheres(n: u32) {
+fn make_n_spheres_scene(n: u32) -> HittableScene {
     let mut scene = HittableScene::new();
     for i in 0..n {
         let x = 10000.0 - 2.0 * (i as f32);
         let sphere = Sphere::new(Vector3D::new(x, 0.0, 0.0), 0.5, Material::None);
-        scene.add(Rc::new(sphere));
+        scene.add(Arc::new(sphere));
     }
+    scene
+}
+
+fn hit_n_spheres(scene: &impl Hittable) {
     let ray = Ray::new(Vector3D::zero(), Vector3D::unit_x());
     scene.hit(&ray, 0.00001, f32::MAX);
 }
 
 fn lambertian_material_scatter() {
-    let material = Material::Lambertan(ColorRGB::new(0.5, 0.5, 0.5));
+    let material = Material::Lambertan(ColorRGB::new(0.5, 0.5, 0.5).into());
     let incoming_ray = Ray::new(Vector3D::new(-1.0, -1.0, 0.0), Vector3D::new(1.0, 1.0, 0.0));
     let hit_record = HitRecord::new(Vector3D::zero(), 1.0, -Vector3D::unit_x(), &material);
-    Material::scatter(&incoming_ray, &hit_record);
+    Material::scatter(&incoming_ray, &hit_record, &mut rand::thread_rng());
 }
 
 fn metal_material_scatter() {
-    let material = Material::Metal(ColorRGB::new(0.5, 0.5, 0.5), 0.5);
+    let material = Material::Metal(ColorRGB::new(0.5, 0.5, 0.5).into(), 0.5);
     let incoming_ray = Ray::new(Vector3D::new(-1.0, -1.0, 0.0), Vector3D::new(1.0, 1.0, 0.0));
     let hit_record = HitRecord::new(Vector3D::zero(), 1.0, -Vector3D::unit_x(), &material);
-    Material::scatter(&incoming_ray, &hit_record);
+    Material::scatter(&incoming_ray, &hit_record, &mut rand::thread_rng());
 }
 
 fn dielectric_material_scatter() {
     let material = Material::Dielectric(0.5);
     let incoming_ray = Ray::new(Vector3D::new(-1.0, -1.0, 0.0), Vector3D::new(1.0, 1.0, 0.0));
     let hit_record = HitRecord::new(Vector3D::zero(), 1.0, -Vector3D::unit_x(), &material);
-    Material::scatter(&incoming_ray, &hit_record);
+    Material::scatter(&incoming_ray, &hit_record, &mut rand::thread_rng());
 }
 
 fn no_material_scatter() {
     let material = Material::None;
     let incoming_ray = Ray::new(Vector3D::new(-1.0, -1.0, 0.0), Vector3D::new(1.0, 1.0, 0.0));
     let hit_record = HitRecord::new(Vector3D::zero(), 1.0, -Vector3D::unit_x(), &material);
-    Material::scatter(&incoming_ray, &hit_record);
+    Material::scatter(&incoming_ray, &hit_record, &mut rand::thread_rng());
 }
 
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("random vector", |b| b.iter(make_random_vector));
     c.bench_function("unit sphere sample", |b| b.iter(unit_sphere_sample));
     c.bench_function("hit 1 sphere", |b| b.iter(hit_one_sphere));
-    c.bench_function("hit n spheres", |b| b.iter(|| hit_n_spheres(black_box(5))));
+    for n in [5, 500] {
+        let scene = make_n_spheres_scene(n);
+        c.bench_function(&format!("hit {} spheres", n), |b| {
+            b.iter(|| hit_n_spheres(black_box(&scene)))
+        });
+
+        let bvh = make_n_spheres_scene(n).into_bvh();
+        c.bench_function(&format!("hit {} spheres bvh", n), |b| {
+            b.iter(|| hit_n_spheres(black_box(&bvh)))
+        });
+    }
     c.bench_function("lambertan scatter", |b| b.iter(lambertian_material_scatter));
     c.bench_function("metal scatter", |b| b.iter(metal_material_scatter));
     c.bench_function("dielectric_scatter", |b| {
@@ -1,26 +1,39 @@
 use rand::Rng;
+use std::sync::Arc;
 use yarrr::prelude::*;
 
-fn create_scene() -> SphereScene {
-    let mut scene = SphereScene::new();
+fn create_scene() -> HittableScene {
+    let mut scene = HittableScene::new();
 
     // ground
-    let m_ground = Material::Lambertan(ColorRGB::new(0.5, 0.5, 0.5));
-    scene.add(Sphere::new(
-        Vector3D::new(0.0, -1000.0, 0.0),
-        1000.0,
+    let m_ground = Material::Lambertan(ColorRGB::new(0.5, 0.5, 0.5).into());
+    scene.add(Arc::new(Plane::new(
+        Vector3D::zero(),
+        Vector3D::unit_y(),
         m_ground,
-    ));
+    )));
 
     // 3 big beautiful spheres
     let material1 = Material::Dielectric(1.5);
-    scene.add(Sphere::new(Vector3D::new(0.0, 1.0, 0.0), 1.0, material1));
+    scene.add(Arc::new(Sphere::new(
+        Vector3D::new(0.0, 1.0, 0.0),
+        1.0,
+        material1,
+    )));
 
-    let material2 = Material::Lambertan(ColorRGB::new(0.4, 0.2, 0.1));
-    scene.add(Sphere::new(Vector3D::new(-4.0, 1.0, 0.0), 1.0, material2));
+    let material2 = Material::Lambertan(ColorRGB::new(0.4, 0.2, 0.1).into());
+    scene.add(Arc::new(Sphere::new(
+        Vector3D::new(-4.0, 1.0, 0.0),
+        1.0,
+        material2,
+    )));
 
-    let material3 = Material::Metal(ColorRGB::new(0.7, 0.6, 0.5), 0.0);
-    scene.add(Sphere::new(Vector3D::new(4.0, 1.0, 0.0), 1.0, material3));
+    let material3 = Material::Metal(ColorRGB::new(0.7, 0.6, 0.5).into(), 0.0);
+    scene.add(Arc::new(Sphere::new(
+        Vector3D::new(4.0, 1.0, 0.0),
+        1.0,
+        material3,
+    )));
 
     // random small spheres on a grid with random materials
     let mut rng = rand::thread_rng();
@@ -37,19 +50,20 @@ fn create_scene() -> SphereScene {
                 let material = match material_rand {
                     // diffuse
                     0..=79 => {
-                        let albedo = ColorRGB::random(0.0, 1.0) * ColorRGB::random(0.0, 1.0);
-                        Material::Lambertan(albedo)
+                        let albedo = ColorRGB::random(0.0, 1.0, &mut rng)
+                            * ColorRGB::random(0.0, 1.0, &mut rng);
+                        Material::Lambertan(albedo.into())
                     }
                     // metal
                     80..=94 => {
-                        let albedo = ColorRGB::random(0.5, 1.0);
+                        let albedo = ColorRGB::random(0.5, 1.0, &mut rng);
                         let fuzz = rng.gen_range(0.0..0.5);
-                        Material::Metal(albedo, fuzz)
+                        Material::Metal(albedo.into(), fuzz)
                     }
                     // glass
                     _ => Material::Dielectric(1.5),
                 };
-                scene.add(Sphere::new(center, 0.2, material));
+                scene.add(Arc::new(Sphere::new(center, 0.2, material)));
             }
         }
     }
@@ -66,29 +80,24 @@ fn main() {
         Vector3D::unit_y(),
         vfov,
         aspect_ratio,
-    );
+    )
+    .with_focus(0.1, 10.0);
 
     let width = 1600;
     let height = (width as f32 / aspect_ratio) as u32;
     let mut im = Image::new(width, height);
 
     // scene
-    let scene = create_scene();
+    let scene = create_scene().into_bvh();
 
     // render
     let settings = RenderSettings {
         samples_per_px: 100,
         bounce_depth: 5,
+        ..Default::default()
     };
     color_image(&mut im, cam, scene, settings);
     // print_ppm(&im);
 
-    image::save_buffer(
-        "final_render.jpeg",
-        &im.as_bytes(),
-        im.width,
-        im.height,
-        image::ColorType::Rgb8,
-    )
-    .expect("Ünable to save image");
+    im.save("final_render.jpeg").expect("Unable to save image");
 }
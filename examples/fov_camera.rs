@@ -1,10 +1,10 @@
 use yarrr::prelude::*;
 
 fn create_scene() -> SphereScene {
-    let m_left = Material::Metal(ColorRGB::new(0.8, 0.6, 0.2), 0.3);
-    let m_right = Material::Metal(ColorRGB::new(0.0, 0.6, 0.5), 0.0);
-    let m_center = Material::Lambertan(ColorRGB::new(1.0, 1.0, 0.0));
-    let m_ground = Material::Lambertan(ColorRGB::new(0.2, 0.9, 0.4));
+    let m_left = Material::Metal(ColorRGB::new(0.8, 0.6, 0.2).into(), 0.3);
+    let m_right = Material::Metal(ColorRGB::new(0.0, 0.6, 0.5).into(), 0.0);
+    let m_center = Material::Lambertan(ColorRGB::new(1.0, 1.0, 0.0).into());
+    let m_ground = Material::Lambertan(ColorRGB::new(0.2, 0.9, 0.4).into());
 
     // create scene
     let mut scene = SphereScene::new();
@@ -42,15 +42,9 @@ fn main() {
     let settings = RenderSettings {
         samples_per_px: 100,
         bounce_depth: 5,
+        ..Default::default()
     };
     color_image(&mut im, cam, scene, settings);
 
-    image::save_buffer(
-        "fov.jpeg",
-        &im.as_bytes(),
-        im.width,
-        im.height,
-        image::ColorType::Rgb8,
-    )
-    .expect("Ünable to save image");
+    im.save("fov.jpeg").expect("Unable to save image");
 }
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+use yarrr::prelude::*;
+
+fn create_scene() -> HittableScene {
+    let m_light = Material::DiffuseLight(ColorRGB::new(1.0, 0.9, 0.7), 4.0);
+    let m_center = Material::Lambertan(ColorRGB::new(0.2, 0.1, 0.9).into());
+    let m_right = Material::Metal(ColorRGB::new(0.8, 0.8, 0.8).into(), 0.1);
+    let m_left = Material::Dielectric(1.5);
+    let m_ground = Material::Lambertan(ColorRGB::new(0.5, 0.5, 0.5).into());
+
+    let mut scene = HittableScene::new();
+    scene.add_light(Arc::new(Sphere::new(
+        Vector3D::new(0.0, 1.5, -1.0),
+        0.5,
+        m_light,
+    )));
+    scene.add(Arc::new(Sphere::new(
+        Vector3D::new(-1.0, 0.0, -1.0),
+        0.5,
+        m_left,
+    )));
+    scene.add(Arc::new(Sphere::new(
+        Vector3D::new(1.0, 0.0, -1.0),
+        0.5,
+        m_right,
+    )));
+    scene.add(Arc::new(Sphere::new(
+        Vector3D::new(0.0, 0.0, -1.0),
+        0.5,
+        m_center,
+    )));
+    scene.add(Arc::new(Sphere::new(
+        Vector3D::new(0.0, -100.5, -1.0),
+        100.0,
+        m_ground,
+    )));
+    scene
+}
+
+fn render(light_sampling: LightSampling, path: &str) {
+    let aspect_ratio = 16.0 / 9.0;
+    let vfov = 45.0;
+    let cam = FovCamera::new(
+        Vector3D::new(0.0, 1.0, 3.0),
+        -Vector3D::unit_z(),
+        Vector3D::unit_y(),
+        vfov,
+        aspect_ratio,
+    );
+
+    let width = 1600;
+    let height = (width as f32 / aspect_ratio) as u32;
+    let mut im = Image::new(width, height);
+
+    // scene lit only by the emissive sphere
+    let scene = create_scene();
+
+    // render
+    let settings = RenderSettings {
+        samples_per_px: 400,
+        bounce_depth: 10,
+        environment: Environment::Solid(ColorRGB::zero()),
+        light_sampling,
+        ..Default::default()
+    };
+    color_image(&mut im, cam, scene.into_bvh(), settings);
+
+    im.save(path).expect("Unable to save image");
+}
+
+fn main() {
+    // same sample count with shadow rays and with the pure path tracer
+    render(LightSampling::NextEvent, "lights.jpeg");
+    render(LightSampling::None, "lights_path_traced.jpeg");
+}
@@ -1,10 +1,10 @@
 use yarrr::prelude::*;
 
 fn create_scene() -> SphereScene {
-    let m_right = Material::Metal(ColorRGB::new(0.8, 0.8, 0.8), 0.2);
-    let m_left = Material::Metal(ColorRGB::new(0.8, 0.0, 0.4), 0.0);
-    let m_center = Material::Lambertan(ColorRGB::new(0.2, 0.1, 0.9));
-    let m_ground = Material::Lambertan(ColorRGB::new(0.2, 0.9, 0.4));
+    let m_right = Material::Metal(ColorRGB::new(0.8, 0.8, 0.8).into(), 0.2);
+    let m_left = Material::Metal(ColorRGB::new(0.8, 0.0, 0.4).into(), 0.0);
+    let m_center = Material::Lambertan(ColorRGB::new(0.2, 0.1, 0.9).into());
+    let m_ground = Material::Lambertan(ColorRGB::new(0.2, 0.9, 0.4).into());
 
     let mut scene = SphereScene::new();
     scene.add(Sphere::new(Vector3D::new(-1.0, -0.0, -1.0), 0.5, m_left));
@@ -38,16 +38,10 @@ fn main() {
     let settings = RenderSettings {
         samples_per_px: 100,
         bounce_depth: 5,
+        ..Default::default()
     };
     color_image(&mut im, cam, scene, settings);
 
     // save results
-    image::save_buffer(
-        "materials.jpeg",
-        &im.as_bytes(),
-        im.width,
-        im.height,
-        image::ColorType::Rgb8,
-    )
-    .expect("Unable to save image");
+    im.save("materials.jpeg").expect("Unable to save image");
 }
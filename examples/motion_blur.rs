@@ -0,0 +1,57 @@
+use std::sync::Arc;
+use yarrr::prelude::*;
+
+fn create_scene() -> HittableScene {
+    let m_ground = Material::Lambertan(ColorRGB::new(0.5, 0.5, 0.5).into());
+    let m_moving = Material::Lambertan(ColorRGB::new(0.2, 0.1, 0.9).into());
+    let m_still = Material::Metal(ColorRGB::new(0.8, 0.8, 0.8).into(), 0.1);
+
+    let mut scene = HittableScene::new();
+    scene.add(Arc::new(Plane::new(
+        Vector3D::new(0.0, -0.5, 0.0),
+        Vector3D::unit_y(),
+        m_ground,
+    )));
+    // sphere bouncing up while the shutter is open
+    scene.add(Arc::new(MovingSphere::new(
+        Vector3D::new(-0.6, 0.0, -1.0),
+        Vector3D::new(-0.6, 0.4, -1.0),
+        0.0,
+        1.0,
+        0.5,
+        m_moving,
+    )));
+    scene.add(Arc::new(Sphere::new(
+        Vector3D::new(0.6, 0.0, -1.0),
+        0.5,
+        m_still,
+    )));
+    scene
+}
+
+fn main() {
+    let aspect_ratio = 16.0 / 9.0;
+    let vfov = 45.0;
+    let cam = FovCamera::new(
+        Vector3D::new(0.0, 1.0, 3.0),
+        -Vector3D::unit_z(),
+        Vector3D::unit_y(),
+        vfov,
+        aspect_ratio,
+    )
+    .with_shutter(Shutter::new(0.0, 1.0));
+
+    let width = 800;
+    let height = (width as f32 / aspect_ratio) as u32;
+    let mut im = Image::new(width, height);
+
+    // render
+    let scene = create_scene().into_bvh();
+    let settings = RenderSettings {
+        samples_per_px: 200,
+        ..Default::default()
+    };
+    color_image(&mut im, cam, scene, settings);
+
+    im.save("motion_blur.jpeg").expect("Unable to save image");
+}
@@ -0,0 +1,59 @@
+use yarrr::prelude::*;
+
+fn create_scene() -> SphereScene {
+    let m_left = Material::Metal(ColorRGB::new(0.8, 0.6, 0.2).into(), 0.3);
+    let m_right = Material::Metal(ColorRGB::new(0.0, 0.6, 0.5).into(), 0.0);
+    let m_center = Material::Lambertan(ColorRGB::new(1.0, 1.0, 0.0).into());
+    let m_ground = Material::Lambertan(ColorRGB::new(0.2, 0.9, 0.4).into());
+
+    // create scene
+    let mut scene = SphereScene::new();
+    scene.add(Sphere::new(Vector3D::new(-1.0, -0.0, -1.0), 0.5, m_left));
+    scene.add(Sphere::new(Vector3D::new(1.0, 0.0, -1.0), 0.5, m_right));
+    scene.add(Sphere::new(Vector3D::new(0.0, 0.0, -1.0), 0.5, m_center));
+    scene.add(Sphere::new(
+        Vector3D::new(0.0, -100.5, -1.0),
+        100.0,
+        m_ground,
+    ));
+
+    scene
+}
+
+fn render(camera: impl Camera, width: u32, height: u32, path: &str) {
+    let mut im = Image::new(width, height);
+    let settings = RenderSettings {
+        samples_per_px: 100,
+        bounce_depth: 5,
+        ..Default::default()
+    };
+    color_image(&mut im, camera, create_scene().into_bvh(), settings);
+
+    im.save(path).expect("Unable to save image");
+}
+
+fn main() {
+    let origin = Vector3D::new(0.0, 0.5, 1.0);
+    let lookat = Vector3D::new(0.0, 0.0, -1.0);
+    let vup = Vector3D::unit_y();
+
+    let aspect_ratio = 16.0 / 9.0;
+    render(
+        OrthographicCamera::new(origin, lookat, vup, 2.0, aspect_ratio),
+        800,
+        450,
+        "orthographic.jpeg",
+    );
+    render(
+        FisheyeCamera::new(origin, lookat, vup, 180.0, aspect_ratio),
+        800,
+        450,
+        "fisheye.jpeg",
+    );
+    render(
+        PanoramaCamera::new(origin, lookat, vup),
+        800,
+        400,
+        "panorama.jpeg",
+    );
+}
@@ -0,0 +1,65 @@
+use yarrr::prelude::*;
+
+fn create_scene() -> SphereScene {
+    let checker = Texture::checker(
+        ColorRGB::new(0.2, 0.3, 0.1),
+        ColorRGB::new(0.9, 0.9, 0.9),
+        0.5,
+    );
+    let marble = Texture::noise(
+        42,
+        NoisePattern::Marble(7),
+        4.0,
+        ColorRGB::new(1.0, 1.0, 1.0),
+    );
+    let turbulence = Texture::noise(
+        42,
+        NoisePattern::Turbulence(7),
+        4.0,
+        ColorRGB::new(0.9, 0.6, 0.3),
+    );
+
+    let mut scene = SphereScene::new();
+    scene.add(Sphere::new(
+        Vector3D::new(-1.0, 0.0, -1.0),
+        0.5,
+        Material::Lambertan(marble),
+    ));
+    scene.add(Sphere::new(
+        Vector3D::new(1.0, 0.0, -1.0),
+        0.5,
+        Material::Metal(turbulence, 0.2),
+    ));
+    scene.add(Sphere::new(
+        Vector3D::new(0.0, -100.5, -1.0),
+        100.0,
+        Material::Lambertan(checker),
+    ));
+    scene
+}
+
+fn main() {
+    let aspect_ratio = 16.0 / 9.0;
+    let vfov = 45.0;
+    let cam = FovCamera::new(
+        Vector3D::new(0.0, 1.0, 2.0),
+        -Vector3D::unit_z(),
+        Vector3D::unit_y(),
+        vfov,
+        aspect_ratio,
+    );
+
+    let width = 800;
+    let height = (width as f32 / aspect_ratio) as u32;
+    let mut im = Image::new(width, height);
+
+    // render
+    let settings = RenderSettings {
+        samples_per_px: 100,
+        seed: Some(42),
+        ..Default::default()
+    };
+    color_image(&mut im, cam, create_scene(), settings);
+
+    im.save("textures.jpeg").expect("Unable to save image");
+}
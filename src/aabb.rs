@@ -0,0 +1,103 @@
+use crate::prelude::*;
+
+/// Axis aligned bounding box described by its
+/// minimum and maximum corners
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3D,
+    pub max: Vector3D,
+}
+
+impl Aabb {
+    pub fn new(min: Vector3D, max: Vector3D) -> Self {
+        Self { min, max }
+    }
+
+    /// smallest box containing both boxes
+    ///
+    pub fn union(&self, other: &Aabb) -> Self {
+        Self {
+            min: Vector3D::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            max: Vector3D::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        }
+    }
+
+    pub fn center(&self) -> Vector3D {
+        (self.min + self.max) / 2.0
+    }
+
+    /// index of the axis along which the box is the longest
+    ///
+    pub fn longest_axis(&self) -> usize {
+        let extent = self.max - self.min;
+        if extent.x > extent.y && extent.x > extent.z {
+            0
+        } else if extent.y > extent.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// slab method checking wether the ray passes through the box
+    /// within t_min and t_max
+    /// https://raytracing.github.io/books/RayTracingTheNextWeek.html#boundingvolumehierarchies/rayintersectionwithanaabb
+    ///
+    pub fn hit(&self, ray: &Ray, mut t_min: f32, mut t_max: f32) -> bool {
+        for axis in 0..3 {
+            let inv_d = 1.0 / ray.direction[axis];
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inv_d;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t0.max(t_min);
+            t_max = t1.min(t_max);
+            if t_max <= t_min {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aabb_union() {
+        let a = Aabb::new(Vector3D::new(0.0, 0.0, 0.0), Vector3D::new(1.0, 1.0, 1.0));
+        let b = Aabb::new(Vector3D::new(-1.0, 0.5, 0.5), Vector3D::new(0.5, 1.5, 0.5));
+        let c = a.union(&b);
+        assert_vec_eq(&c.min, &Vector3D::new(-1.0, 0.0, 0.0));
+        assert_vec_eq(&c.max, &Vector3D::new(1.0, 1.5, 1.0));
+        assert_eq!(c.longest_axis(), 0);
+    }
+
+    #[test]
+    fn test_aabb_hit() {
+        let a = Aabb::new(
+            Vector3D::new(-1.0, -1.0, -6.0),
+            Vector3D::new(1.0, 1.0, -4.0),
+        );
+        let ray = Ray::new(Vector3D::zero(), -Vector3D::unit_z());
+        assert!(a.hit(&ray, 0.0, f32::INFINITY));
+        assert!(!a.hit(&ray, 0.0, 3.0));
+
+        let ray = Ray::new(Vector3D::zero(), Vector3D::unit_z());
+        assert!(!a.hit(&ray, 0.0, f32::INFINITY));
+
+        let ray = Ray::new(Vector3D::new(2.0, 0.0, 0.0), -Vector3D::unit_z());
+        assert!(!a.hit(&ray, 0.0, f32::INFINITY));
+    }
+}
@@ -0,0 +1,282 @@
+use crate::prelude::*;
+use std::fmt;
+
+/// Bounds of the per pixel sample count when sampling stops early once the
+/// pixel estimate is precise enough, see `RenderSettings::adaptive`
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AdaptiveSampling {
+    /// samples taken before the error is estimated, at least 2
+    pub min_samples: u32,
+    pub max_samples: u32,
+    /// largest accepted standard error of the mean pixel luminance
+    /// relative to the luminance itself
+    pub threshold: f32,
+}
+
+/// Invalid adaptive sampling settings
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdaptiveError {
+    /// sample bounds are not an increasing range of at least 2 samples
+    Samples { min: u32, max: u32 },
+    /// noise threshold is not positive
+    Threshold(f32),
+}
+
+impl fmt::Display for AdaptiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdaptiveError::Samples { min, max } => write!(
+                f,
+                "adaptive samples {}..{} are not an increasing range starting at 2 or more",
+                min, max
+            ),
+            AdaptiveError::Threshold(threshold) => {
+                write!(f, "adaptive noise threshold {} is not positive", threshold)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AdaptiveError {}
+
+impl AdaptiveSampling {
+    /// error if the bounds are not an increasing range
+    /// of at least 2 samples or the threshold is not positive
+    ///
+    pub fn validate(&self) -> Result<(), AdaptiveError> {
+        if self.min_samples < 2 || self.max_samples < self.min_samples {
+            return Err(AdaptiveError::Samples {
+                min: self.min_samples,
+                max: self.max_samples,
+            });
+        }
+        if self.threshold.is_nan() || self.threshold <= 0.0 {
+            return Err(AdaptiveError::Threshold(self.threshold));
+        }
+        Ok(())
+    }
+}
+
+impl Default for AdaptiveSampling {
+    fn default() -> Self {
+        Self {
+            min_samples: 16,
+            max_samples: 1024,
+            threshold: 0.01,
+        }
+    }
+}
+
+/// Running mean and variance of the samples of a single pixel
+/// https://en.wikipedia.org/wiki/Algorithms_for_calculating_variance#Welford's_online_algorithm
+///
+#[derive(Debug, Copy, Clone, Default)]
+pub(crate) struct PixelEstimate {
+    count: u32,
+    sum: ColorRGB,
+    mean_luminance: f32,
+    /// sum of squared luminance differences from the mean
+    m2: f32,
+}
+
+impl PixelEstimate {
+    /// relative errors of pixels darker than this are measured against it,
+    /// noise in almost black pixels is not visible
+    const DARK_LUMINANCE: f32 = 0.05;
+
+    pub fn add(&mut self, color: ColorRGB) {
+        let luminance = luminance(&color);
+        self.count += 1;
+        self.sum += color;
+        let delta = luminance - self.mean_luminance;
+        self.mean_luminance += delta / self.count as f32;
+        self.m2 += delta * (luminance - self.mean_luminance);
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn mean(&self) -> ColorRGB {
+        self.sum * (1.0 / self.count.max(1) as f32)
+    }
+
+    /// standard error of the mean luminance relative to the luminance
+    ///
+    pub fn relative_error(&self) -> f32 {
+        if self.count < 2 {
+            return f32::INFINITY;
+        }
+        let variance = self.m2 / (self.count - 1) as f32;
+        (variance / self.count as f32).sqrt() / self.mean_luminance.max(Self::DARK_LUMINANCE)
+    }
+
+    /// whether the pixel needs no more samples
+    ///
+    pub fn is_done(&self, adaptive: &AdaptiveSampling) -> bool {
+        self.count >= adaptive.max_samples
+            || (self.count >= adaptive.min_samples.max(2)
+                && self.relative_error() <= adaptive.threshold)
+    }
+}
+
+fn luminance(color: &ColorRGB) -> f32 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+/// Number of samples taken for every image pixel
+///
+pub struct SampleCounts {
+    width: u32,
+    height: u32,
+    counts: Vec<u32>,
+}
+
+impl SampleCounts {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            counts: vec![0; (width * height) as usize],
+        }
+    }
+
+    pub fn dims(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn at(&self, i: u32, j: u32) -> u32 {
+        self.counts[(j * self.width + i) as usize]
+    }
+
+    pub fn set_at(&mut self, i: u32, j: u32, count: u32) {
+        self.counts[(j * self.width + i) as usize] = count;
+    }
+
+    /// samples taken for the whole image
+    ///
+    pub fn total(&self) -> u64 {
+        self.counts.iter().map(|&c| c as u64).sum()
+    }
+
+    /// image of the sample counts, from blue for pixels with `min_samples`
+    /// to red for pixels with `max_samples`, oriented like the rendered image,
+    /// heatmaps of renders with the same bounds are comparable
+    ///
+    pub fn heatmap(&self, min_samples: u32, max_samples: u32) -> Image {
+        let range = max_samples.saturating_sub(min_samples).max(1) as f32;
+        let mut image = Image::new(self.width, self.height);
+        for j in 0..self.height {
+            for i in 0..self.width {
+                let count = self
+                    .at(i, j)
+                    .clamp(min_samples, max_samples.max(min_samples));
+                let t = (count - min_samples) as f32 / range;
+                image.set_at(i, j, ColorRGB::new(t, 0.0, 1.0 - t));
+            }
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_mean_and_error() {
+        let mut estimate = PixelEstimate::default();
+        assert_eq!(estimate.relative_error(), f32::INFINITY);
+        for value in [1.0, 3.0, 1.0, 3.0] {
+            estimate.add(ColorRGB::new(value, value, value));
+        }
+        assert_eq!(estimate.count(), 4);
+        assert_vec_eq(&estimate.mean(), &ColorRGB::new(2.0, 2.0, 2.0));
+        // sample variance 4/3, standard error sqrt(1/3), mean luminance 2
+        assert_almost_eq(estimate.relative_error(), (1.0f32 / 3.0).sqrt() / 2.0);
+    }
+
+    #[test]
+    fn test_estimate_stops_within_bounds() {
+        let adaptive = AdaptiveSampling {
+            min_samples: 4,
+            max_samples: 8,
+            threshold: 0.01,
+        };
+        // constant pixels stop at the minimum
+        let mut flat = PixelEstimate::default();
+        while !flat.is_done(&adaptive) {
+            flat.add(ColorRGB::new(0.5, 0.5, 0.5));
+        }
+        assert_eq!(flat.count(), 4);
+
+        // noisy pixels stop at the maximum
+        let mut noisy = PixelEstimate::default();
+        let mut k = 0;
+        while !noisy.is_done(&adaptive) {
+            noisy.add(ColorRGB::new(1.0, 1.0, 1.0) * (k % 2) as f32);
+            k += 1;
+        }
+        assert_eq!(noisy.count(), 8);
+
+        // black pixels are measured against the dark luminance floor
+        let mut dark = PixelEstimate::default();
+        for k in 0..4 {
+            dark.add(ColorRGB::new(1e-4, 1e-4, 1e-4) * (k % 2) as f32);
+        }
+        assert!(dark.is_done(&adaptive));
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let mut counts = SampleCounts::new(3, 1);
+        counts.set_at(0, 0, 16);
+        counts.set_at(1, 0, 28);
+        counts.set_at(2, 0, 64);
+        assert_eq!(counts.total(), 108);
+        let heatmap = counts.heatmap(16, 64);
+        assert_vec_eq(&heatmap.at(0, 0), &ColorRGB::new(0.0, 0.0, 1.0));
+        assert_vec_eq(&heatmap.at(1, 0), &ColorRGB::new(0.25, 0.0, 0.75));
+        assert_vec_eq(&heatmap.at(2, 0), &ColorRGB::new(1.0, 0.0, 0.0));
+
+        // colors are relative to the bounds, not to the counts of the render
+        let heatmap = counts.heatmap(16, 112);
+        assert_vec_eq(&heatmap.at(2, 0), &ColorRGB::new(0.5, 0.0, 0.5));
+
+        // uniformly sampled images are all blue at the lower bound
+        let mut uniform = SampleCounts::new(2, 2);
+        for (i, j) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+            uniform.set_at(i, j, 16);
+        }
+        let heatmap = uniform.heatmap(16, 64);
+        assert_vec_eq(&heatmap.at(1, 1), &ColorRGB::new(0.0, 0.0, 1.0));
+        let heatmap = uniform.heatmap(16, 16);
+        assert_vec_eq(&heatmap.at(1, 1), &ColorRGB::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(AdaptiveSampling::default().validate().is_ok());
+        for (min_samples, max_samples, threshold) in
+            [(1, 8, 0.1), (8, 4, 0.1), (2, 8, 0.0), (2, 8, f32::NAN)]
+        {
+            let adaptive = AdaptiveSampling {
+                min_samples,
+                max_samples,
+                threshold,
+            };
+            assert!(adaptive.validate().is_err(), "{:?}", adaptive);
+        }
+        let adaptive = AdaptiveSampling {
+            min_samples: 8,
+            max_samples: 4,
+            threshold: 0.1,
+        };
+        assert_eq!(
+            adaptive.validate(),
+            Err(AdaptiveError::Samples { min: 8, max: 4 })
+        );
+    }
+}
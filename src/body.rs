@@ -1,5 +1,7 @@
 use crate::prelude::*;
-use std::rc::Rc;
+use rand::{Rng, RngCore};
+use std::f32::consts::PI;
+use std::sync::Arc;
 
 /// Container for simplest hittable object
 /// sphere with a material
@@ -20,51 +22,208 @@ impl Sphere {
     }
 }
 
+/// hitting a single sphere can be solved in constant time
+/// solving a quadratic equation
+///
+fn hit_sphere<'a>(
+    center: &Vector3D,
+    radius: f32,
+    material: &'a Material,
+    ray: &Ray,
+    t_min: f32,
+    t_max: f32,
+) -> Option<HitRecord<'a>> {
+    let sphere_dir = ray.origin - *center;
+    // components of quadratic eq
+    let a = ray.direction.norm_squared();
+    let half_b = sphere_dir.dot(&ray.direction);
+    let c = sphere_dir.norm_squared() - radius * radius;
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    // closest intersection
+    let mut t = (-half_b - discriminant.sqrt()) / a;
+    if (t < t_min) | (t > t_max) {
+        t = (-half_b + discriminant.sqrt()) / a;
+        if (t < t_min) | (t > t_max) {
+            return None;
+        }
+    }
+    let point = ray.at(t);
+    let normal = (point - *center).unit();
+    let (u, v) = sphere_uv(&normal);
+    let mut record = HitRecord::new(point, t, normal, material).with_uv(u, v);
+    record.set_ray_facing_normal(ray);
+    Some(record)
+}
+
+/// spherical (u, v) coordinates of the point on the unit sphere,
+/// u goes around Y axis starting at -X and v from the bottom to the top
+///
+fn sphere_uv(p: &Vector3D) -> (f32, f32) {
+    let theta = (-p.y).clamp(-1.0, 1.0).acos();
+    let phi = (-p.z).atan2(p.x) + PI;
+    (phi / (2.0 * PI), theta / PI)
+}
+
 impl Hittable for Sphere {
-    /// hitting a single sphere can be solved in constant time
-    /// solving a quadratic equation
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        hit_sphere(&self.center, self.radius, &self.material, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = Vector3D::new(self.radius, self.radius, self.radius);
+        Some(Aabb::new(self.center - r, self.center + r))
+    }
+}
+
+impl Light for Sphere {
+    /// uniform direction inside the cone of directions hitting the sphere
     ///
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
-        let sphere_dir = ray.origin - self.center;
-        // components of quadratic eq
-        let a = ray.direction.norm_squared();
-        let half_b = sphere_dir.dot(&ray.direction);
-        let c = sphere_dir.norm_squared() - self.radius * self.radius;
-        let discriminant = half_b * half_b - a * c;
-        if discriminant < 0.0 {
-            return None;
+    fn sample_direction(&self, origin: &Vector3D, rng: &mut dyn RngCore) -> Vector3D {
+        let to_center = self.center - *origin;
+        let distance_squared = to_center.norm_squared();
+        if distance_squared <= self.radius * self.radius {
+            return Vector3D::unit_sphere_sample(rng);
         }
+        let cos_max = (1.0 - self.radius * self.radius / distance_squared).sqrt();
+        let cos_theta = 1.0 + rng.gen::<f32>() * (cos_max - 1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * rng.gen::<f32>();
 
-        // closest intersection
-        let mut t = (-half_b - discriminant.sqrt()) / a;
-        if (t < t_min) | (t > t_max) {
-            t = (-half_b + discriminant.sqrt()) / a;
-            if (t < t_min) | (t > t_max) {
-                return None;
-            }
+        let axis = to_center.unit();
+        let (tangent, bitangent) = tangent_frame(&axis);
+        tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + axis * cos_theta
+    }
+
+    fn pdf(&self, origin: &Vector3D, direction: &Vector3D) -> f32 {
+        let distance_squared = (self.center - *origin).norm_squared();
+        if distance_squared <= self.radius * self.radius {
+            return 1.0 / (4.0 * PI);
+        }
+        let ray = Ray::new(*origin, *direction);
+        if self.hit(&ray, 1e-4, f32::INFINITY).is_none() {
+            return 0.0;
         }
-        let point = ray.at(t);
-        let normal = (point - self.center).unit();
-        let mut record = HitRecord::new(point, t, normal, &self.material);
-        record.set_ray_facing_normal(ray);
-        Some(record)
+        let cos_max = (1.0 - self.radius * self.radius / distance_squared).sqrt();
+        1.0 / (2.0 * PI * (1.0 - cos_max))
+    }
+}
+
+/// Sphere moving linearly from `center0` at `time0` to `center1` at `time1`,
+/// it rests at `center0` before and at `center1` after the interval
+///
+pub struct MovingSphere {
+    pub center0: Vector3D,
+    pub center1: Vector3D,
+    pub time0: f32,
+    pub time1: f32,
+    pub radius: f32,
+    pub material: Material,
+}
+
+impl MovingSphere {
+    pub fn new(
+        center0: Vector3D,
+        center1: Vector3D,
+        time0: f32,
+        time1: f32,
+        radius: f32,
+        material: Material,
+    ) -> Self {
+        Self {
+            center0,
+            center1,
+            time0,
+            time1,
+            radius,
+            material,
+        }
+    }
+
+    pub fn center(&self, time: f32) -> Vector3D {
+        let duration = self.time1 - self.time0;
+        if duration == 0.0 {
+            return self.center0;
+        }
+        let k = ((time - self.time0) / duration).clamp(0.0, 1.0);
+        self.center0 + (self.center1 - self.center0) * k
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let center = self.center(ray.time);
+        hit_sphere(&center, self.radius, &self.material, ray, t_min, t_max)
+    }
+
+    /// box around the whole path between the two centers
+    ///
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = Vector3D::new(self.radius, self.radius, self.radius);
+        let start = Aabb::new(self.center0 - r, self.center0 + r);
+        let end = Aabb::new(self.center1 - r, self.center1 + r);
+        Some(start.union(&end))
+    }
+}
+
+/// Box enclosing all bodies, None if there are no bodies
+/// or any of them is unbounded
+///
+fn bounding_box_of<'a, T: Hittable + 'a>(bodies: impl Iterator<Item = &'a T>) -> Option<Aabb> {
+    let mut bbox: Option<Aabb> = None;
+    for body in bodies {
+        let body_box = body.bounding_box()?;
+        bbox = Some(bbox.map_or(body_box, |b| b.union(&body_box)));
     }
+    bbox
 }
 
 /// Container for a collection of various hittable objects
 ///
 pub struct HittableScene {
-    bodies: Vec<Rc<dyn Hittable + 'static>>,
+    bodies: Vec<Arc<dyn Hittable + 'static>>,
+    lights: LightList,
 }
 
 impl HittableScene {
     pub fn new() -> Self {
-        Self { bodies: Vec::new() }
+        Self {
+            bodies: Vec::new(),
+            lights: LightList::new(),
+        }
     }
 
-    pub fn add<T: Hittable + 'static>(&mut self, object: Rc<T>) {
+    pub fn add<T: Hittable + 'static>(&mut self, object: Arc<T>) {
         self.bodies.push(object);
     }
+
+    /// add an emitting body that is also sampled directly as a light source
+    ///
+    pub fn add_light<T: Hittable + Light + 'static>(&mut self, light: Arc<T>) {
+        self.lights.add(light.clone());
+        self.bodies.push(light);
+    }
+
+    /// build a bounding volume hierarchy over the bounded scene bodies,
+    /// unbounded bodies like planes stay next to it in the returned scene
+    ///
+    pub fn into_bvh(self) -> HittableScene {
+        let (bounded, unbounded): (Vec<_>, Vec<_>) = self
+            .bodies
+            .into_iter()
+            .partition(|body| body.bounding_box().is_some());
+        let mut scene = HittableScene {
+            bodies: unbounded,
+            lights: self.lights,
+        };
+        if !bounded.is_empty() {
+            scene.add(Arc::new(BvhNode::new(bounded)));
+        }
+        scene
+    }
 }
 
 impl Default for HittableScene {
@@ -77,7 +236,7 @@ impl Hittable for HittableScene {
     /// Naive implementation will find an intersection in O(N) time
     /// where N is object count
     ///
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
         let mut record = None;
         let mut t_closest = t_max;
         for body in self.bodies.iter() {
@@ -88,6 +247,29 @@ impl Hittable for HittableScene {
         }
         record
     }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        bounding_box_of(self.bodies.iter())
+    }
+
+    /// emitters added with `add_light`
+    ///
+    fn lights(&self) -> Option<&LightList> {
+        Some(&self.lights)
+    }
+}
+
+/// scenes are sampled through their emitters, e.g. a transformed
+/// group of bodies is registered as a single light of the outer scene
+///
+impl Light for HittableScene {
+    fn sample_direction(&self, origin: &Vector3D, rng: &mut dyn RngCore) -> Vector3D {
+        self.lights.sample_direction(origin, rng)
+    }
+
+    fn pdf(&self, origin: &Vector3D, direction: &Vector3D) -> f32 {
+        self.lights.pdf(origin, direction)
+    }
 }
 
 /// Container for a scene containing only spheres
@@ -104,6 +286,12 @@ impl SphereScene {
     pub fn add(&mut self, object: Sphere) {
         self.bodies.push(object);
     }
+
+    /// build a bounding volume hierarchy over the scene spheres
+    ///
+    pub fn into_bvh(self) -> BvhNode<Sphere> {
+        BvhNode::new(self.bodies)
+    }
 }
 
 impl Default for SphereScene {
@@ -113,7 +301,7 @@ impl Default for SphereScene {
 }
 
 impl Hittable for SphereScene {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
         let mut record = None;
         let mut t_closest = t_max;
         for body in self.bodies.iter() {
@@ -124,6 +312,10 @@ impl Hittable for SphereScene {
         }
         record
     }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        bounding_box_of(self.bodies.iter())
+    }
 }
 
 #[cfg(test)]
@@ -174,4 +366,102 @@ mod tests {
         assert!(approx_eq!(f32, result.normal.y, 0.0, epsilon = MAX_TOL_F32));
         assert!(approx_eq!(f32, result.normal.z, 1.0, epsilon = MAX_TOL_F32));
     }
+
+    #[test]
+    fn test_into_bvh_keeps_unbounded_bodies() {
+        let mut scene = HittableScene::new();
+        scene.add(Arc::new(Plane::new(
+            Vector3D::new(0.0, -1.0, 0.0),
+            Vector3D::unit_y(),
+            Material::None,
+        )));
+        scene.add(Arc::new(Sphere::new(
+            Vector3D::new(0.0, 0.0, -5.0),
+            1.0,
+            Material::None,
+        )));
+        let bvh = scene.into_bvh();
+        assert!(bvh.bounding_box().is_none());
+
+        let down = Ray::new(Vector3D::zero(), -Vector3D::unit_y());
+        let result = bvh.hit(&down, 0.0, 1000.0).unwrap();
+        assert!(approx_eq!(f32, result.t, 1.0, epsilon = MAX_TOL_F32));
+        let forward = Ray::new(Vector3D::zero(), -Vector3D::unit_z());
+        let result = bvh.hit(&forward, 0.0, 1000.0).unwrap();
+        assert!(approx_eq!(f32, result.t, 4.0, epsilon = MAX_TOL_F32));
+    }
+
+    #[test]
+    fn test_hit_moving_sphere() {
+        let s = MovingSphere::new(
+            Vector3D::new(0.0, 0.0, -5.0),
+            Vector3D::new(2.0, 0.0, -5.0),
+            0.0,
+            1.0,
+            0.5,
+            Material::None,
+        );
+        assert_vec_eq(&s.center(0.5), &Vector3D::new(1.0, 0.0, -5.0));
+
+        let ray = Ray::new(Vector3D::new(2.0, 0.0, 0.0), Vector3D::new(0.0, 0.0, -1.0));
+        assert!(s.hit(&ray, 0.0, 1000.0).is_none());
+        let result = s.hit(&ray.with_time(1.0), 0.0, 1000.0).unwrap();
+        assert!(approx_eq!(f32, result.t, 4.5, epsilon = MAX_TOL_F32));
+        assert!(approx_eq!(f32, result.normal.z, 1.0, epsilon = MAX_TOL_F32));
+
+        let bbox = s.bounding_box().unwrap();
+        assert_vec_eq(&bbox.min, &Vector3D::new(-0.5, -0.5, -5.5));
+        assert_vec_eq(&bbox.max, &Vector3D::new(2.5, 0.5, -4.5));
+    }
+
+    #[test]
+    fn test_moving_sphere_outside_of_its_times() {
+        let moving = || {
+            Arc::new(MovingSphere::new(
+                Vector3D::new(0.0, 0.0, -5.0),
+                Vector3D::new(2.0, 0.0, -5.0),
+                0.0,
+                1.0,
+                0.5,
+                Material::None,
+            ))
+        };
+        assert_vec_eq(&moving().center(-1.0), &Vector3D::new(0.0, 0.0, -5.0));
+        assert_vec_eq(&moving().center(2.0), &Vector3D::new(2.0, 0.0, -5.0));
+
+        // a shutter ending after time1 sees the sphere at its last position
+        let mut scene = HittableScene::new();
+        scene.add(moving());
+        let mut bvh = HittableScene::new();
+        bvh.add(moving());
+        let bvh = bvh.into_bvh();
+        for x in [0.0, 2.0, 4.0] {
+            let ray = Ray::new(Vector3D::new(x, 0.0, 0.0), -Vector3D::unit_z()).with_time(2.0);
+            let plain = scene.hit(&ray, 0.0, 1000.0).map(|hit| hit.t);
+            let accelerated = bvh.hit(&ray, 0.0, 1000.0).map(|hit| hit.t);
+            assert_eq!(plain, accelerated, "ray at x = {}", x);
+            assert_eq!(plain.is_some(), x == 2.0, "ray at x = {}", x);
+        }
+    }
+
+    #[test]
+    fn test_sphere_uv() {
+        let cases = [
+            (Vector3D::new(-1.0, 0.0, 0.0), (0.0, 0.5)),
+            (Vector3D::new(0.0, 0.0, 1.0), (0.25, 0.5)),
+            (Vector3D::new(1.0, 0.0, 0.0), (0.5, 0.5)),
+            (Vector3D::new(0.0, 0.0, -1.0), (0.75, 0.5)),
+            (Vector3D::new(0.0, 1.0, 0.0), (0.5, 1.0)),
+            (Vector3D::new(0.0, -1.0, 0.0), (0.5, 0.0)),
+        ];
+        let s = Sphere::new(Vector3D::zero(), 1.0, Material::None);
+        for (p, (u, v)) in cases {
+            let ray = Ray::new(p * 3.0, -p);
+            let result = s.hit(&ray, 0.0, 1000.0).unwrap();
+            // u wraps around at -X
+            let du = (result.u - u).abs();
+            assert!(du < 1e-5 || (du - 1.0).abs() < 1e-5);
+            assert!(approx_eq!(f32, result.v, v, epsilon = 1e-5));
+        }
+    }
 }
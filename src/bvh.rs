@@ -0,0 +1,150 @@
+use crate::prelude::*;
+
+/// Bounding volume hierarchy node, a binary tree of bounding boxes
+/// with hittable bodies in the leaves that allows to find
+/// an intersection in O(log N) time where N is object count
+///
+pub struct BvhNode<T: Hittable> {
+    bbox: Aabb,
+    content: BvhContent<T>,
+}
+
+enum BvhContent<T: Hittable> {
+    Leaf(T),
+    Branch(Box<BvhNode<T>>, Box<BvhNode<T>>),
+}
+
+impl<T: Hittable> BvhNode<T> {
+    /// build the hierarchy by recursively splitting the bodies
+    /// in half along the longest axis of their bounding box
+    ///
+    /// # Panics
+    ///
+    /// if `bodies` is empty or contains an unbounded body
+    ///
+    pub fn new(bodies: Vec<T>) -> Self {
+        assert!(!bodies.is_empty(), "Unable to build BVH without bodies");
+        let boxed = bodies
+            .into_iter()
+            .map(|body| {
+                let bbox = body
+                    .bounding_box()
+                    .expect("Unable to build BVH with unbounded body");
+                (bbox, body)
+            })
+            .collect();
+        Self::build(boxed)
+    }
+
+    fn build(mut bodies: Vec<(Aabb, T)>) -> Self {
+        if bodies.len() == 1 {
+            let (bbox, body) = bodies.pop().unwrap();
+            return Self {
+                bbox,
+                content: BvhContent::Leaf(body),
+            };
+        }
+
+        // split along the axis where the body centers are spread the most
+        let centers = bodies
+            .iter()
+            .map(|(bbox, _)| Aabb::new(bbox.center(), bbox.center()))
+            .reduce(|acc, bbox| acc.union(&bbox))
+            .unwrap();
+        let axis = centers.longest_axis();
+        bodies.sort_by(|(a, _), (b, _)| a.center()[axis].total_cmp(&b.center()[axis]));
+
+        let right = bodies.split_off(bodies.len() / 2);
+        let left = Self::build(bodies);
+        let right = Self::build(right);
+        Self {
+            bbox: left.bbox.union(&right.bbox),
+            content: BvhContent::Branch(Box::new(left), Box::new(right)),
+        }
+    }
+}
+
+impl<T: Hittable> Hittable for BvhNode<T> {
+    /// only descend into the children whose bounding box is hit
+    ///
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        if !self.bbox.hit(ray, t_min, t_max) {
+            return None;
+        }
+
+        match &self.content {
+            BvhContent::Leaf(body) => body.hit(ray, t_min, t_max),
+            BvhContent::Branch(left, right) => {
+                let left_record = left.hit(ray, t_min, t_max);
+                let t_closest = left_record.as_ref().map_or(t_max, |record| record.t);
+                right.hit(ray, t_min, t_closest).or(left_record)
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn make_spheres(n: u32) -> Vec<Sphere> {
+        (0..n)
+            .map(|i| {
+                let x = 2.0 * i as f32 + 2.0;
+                Sphere::new(Vector3D::new(x, 0.0, 0.0), 0.5, Material::None)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bvh_bounding_box() {
+        let bvh = BvhNode::new(make_spheres(10));
+        let bbox = bvh.bounding_box().unwrap();
+        assert_vec_eq(&bbox.min, &Vector3D::new(1.5, -0.5, -0.5));
+        assert_vec_eq(&bbox.max, &Vector3D::new(20.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn test_bvh_hit_closest() {
+        let bvh = BvhNode::new(make_spheres(10));
+        let ray = Ray::new(Vector3D::new(30.0, 0.0, 0.0), -Vector3D::unit_x());
+        let record = bvh.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert_almost_eq(record.t, 9.5);
+        assert_vec_eq(&record.point, &Vector3D::new(20.5, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_bvh_matches_linear_scene() {
+        let mut scene = SphereScene::new();
+        for sphere in make_spheres(10) {
+            scene.add(sphere);
+        }
+        let bvh = BvhNode::new(make_spheres(10));
+        for dir in [
+            Vector3D::unit_x(),
+            Vector3D::new(1.0, 0.01, 0.0),
+            Vector3D::unit_y(),
+        ] {
+            let ray = Ray::new(Vector3D::new(-1.0, 0.0, 0.0), dir);
+            let expected = scene.hit(&ray, 0.0, f32::INFINITY).map(|r| r.t);
+            let actual = bvh.hit(&ray, 0.0, f32::INFINITY).map(|r| r.t);
+            assert_eq!(expected, actual);
+        }
+    }
+
+    #[test]
+    fn test_bvh_dyn_bodies_miss() {
+        let bodies: Vec<Arc<dyn Hittable>> = make_spheres(3)
+            .into_iter()
+            .map(|sphere| Arc::new(sphere) as Arc<dyn Hittable>)
+            .collect();
+        let bvh = BvhNode::new(bodies);
+        let ray = Ray::new(Vector3D::new(0.0, 2.0, 0.0), Vector3D::unit_x());
+        assert!(bvh.hit(&ray, 0.0, f32::INFINITY).is_none());
+    }
+}
@@ -1,4 +1,6 @@
 use crate::prelude::*;
+use rand::{Rng, RngCore};
+use std::f32::consts::PI;
 
 /// container for image view port which is a plane focal length away from
 /// the camera origin that the rays are shoot through
@@ -15,11 +17,91 @@ impl Viewport {
     }
 }
 
+/// camera coordinate system, horizontal u and vertical v axes
+/// of the image and w axis pointing backwards from `lookat`
+///
+fn camera_basis(
+    origin: &Vector3D,
+    lookat: &Vector3D,
+    vup: &Vector3D,
+) -> (Vector3D, Vector3D, Vector3D) {
+    let w = (origin - lookat).unit();
+    let u = vup.cross(&w).unit();
+    let v = w.cross(&u);
+    (u, v, w)
+}
+
+/// Time interval during which the camera shutter is open,
+/// rays are shot at random times within it to get motion blur
+///
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Shutter {
+    pub open: f32,
+    pub close: f32,
+}
+
+impl Shutter {
+    pub fn new(open: f32, close: f32) -> Self {
+        Self { open, close }
+    }
+
+    /// uniformly distributed time in the interval
+    ///
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f32 {
+        if self.close > self.open {
+            rng.gen_range(self.open..self.close)
+        } else {
+            self.open
+        }
+    }
+}
+
 /// common trait for all cameras that can be used in
-/// the rendering
+/// the rendering, cameras are shared between render threads
+///
+/// the random generator is a trait object, like in `Light`,
+/// to keep cameras usable as `dyn Camera`
 ///
-pub trait Camera {
+pub trait Camera: Send + Sync {
     fn ray_from_uv(&self, u: f32, v: f32) -> Ray;
+
+    /// ray used by the renderer, cameras can randomize the rays
+    /// e.g. over the area of their lens
+    ///
+    fn sample_ray(&self, u: f32, v: f32, _rng: &mut dyn RngCore) -> Ray {
+        self.ray_from_uv(u, v)
+    }
+
+    /// same camera with the shutter open for the time interval
+    ///
+    fn with_shutter(self, shutter: Shutter) -> ShutterCamera<Self>
+    where
+        Self: Sized,
+    {
+        ShutterCamera {
+            camera: self,
+            shutter,
+        }
+    }
+}
+
+/// Camera shooting its rays at random times while the shutter is open,
+/// see `Camera::with_shutter`
+///
+pub struct ShutterCamera<C> {
+    pub camera: C,
+    pub shutter: Shutter,
+}
+
+impl<C: Camera> Camera for ShutterCamera<C> {
+    fn ray_from_uv(&self, u: f32, v: f32) -> Ray {
+        self.camera.ray_from_uv(u, v).with_time(self.shutter.open)
+    }
+
+    fn sample_ray(&self, u: f32, v: f32, rng: &mut dyn RngCore) -> Ray {
+        let ray = self.camera.sample_ray(u, v, rng);
+        ray.with_time(self.shutter.sample(rng))
+    }
 }
 
 /// simple axis alligned camera at origin
@@ -54,15 +136,26 @@ impl Camera for SimpleCamera {
     }
 }
 
-/// field of view camera at origin
+/// field of view camera at origin, by default a pinhole camera with
+/// everything in focus, a lens with a non zero aperture blurs everything
+/// that is not at the focus distance
 ///
 pub struct FovCamera {
     origin: Vector3D,
+    lookat: Vector3D,
+    vup: Vector3D,
     pub vfow: f32,
     pub aspect_ratio: f32,
+    /// lens diameter, 0 for a pinhole camera
+    aperture: f32,
+    /// distance from the origin to the plane in perfect focus
+    focus_distance: f32,
     vp_lower_left_corner: Vector3D,
     vp_horizontal_span: Vector3D,
     vp_vertical_span: Vector3D,
+    // horizontal and vertical unit vectors of the lens plane
+    u: Vector3D,
+    v: Vector3D,
 }
 
 impl FovCamera {
@@ -73,36 +166,408 @@ impl FovCamera {
         vfow: f32,
         aspect_ratio: f32,
     ) -> Self {
-        // build viewport
-        let theta = vfow.to_radians();
-        let h = (theta / 2.0).tan();
-        let viewport = Viewport::new(2.0 * h, aspect_ratio);
+        let (u, v, _) = camera_basis(&origin, &lookat, &vup);
+        let mut camera = Self {
+            origin,
+            lookat,
+            vup,
+            vp_lower_left_corner: Vector3D::zero(),
+            vp_horizontal_span: Vector3D::zero(),
+            vp_vertical_span: Vector3D::zero(),
+            u,
+            v,
+            vfow,
+            aspect_ratio,
+            aperture: 0.0,
+            focus_distance: 1.0,
+        };
+        camera.place_viewport();
+        camera
+    }
+
+    /// build the viewport spanning the field of view on the focus plane
+    ///
+    fn place_viewport(&mut self) {
+        let h = (self.vfow.to_radians() / 2.0).tan();
+        let viewport = Viewport::new(2.0 * h * self.focus_distance, self.aspect_ratio);
+        let w = self.u.cross(&self.v);
+
+        self.vp_horizontal_span = viewport.width * self.u;
+        self.vp_vertical_span = viewport.height * self.v;
+        self.vp_lower_left_corner = self.origin
+            - self.vp_horizontal_span / 2.0
+            - self.vp_vertical_span / 2.0
+            - w * self.focus_distance;
+    }
 
-        // build coordinate system
-        let w = (origin - lookat).unit();
-        let u = vup.cross(&w).unit();
-        let v = w.cross(&u);
+    /// lens diameter, 0 for a pinhole camera
+    ///
+    pub fn aperture(&self) -> f32 {
+        self.aperture
+    }
+
+    /// distance from the origin to the plane in perfect focus
+    ///
+    pub fn focus_distance(&self) -> f32 {
+        self.focus_distance
+    }
+
+    /// same camera with a viewport of a different aspect ratio
+    ///
+    pub fn with_aspect_ratio(&self, aspect_ratio: f32) -> Self {
+        Self::new(self.origin, self.lookat, self.vup, self.vfow, aspect_ratio)
+            .with_focus(self.aperture, self.focus_distance)
+    }
+
+    /// same camera with a thin lens of the `aperture` diameter focused
+    /// at the `focus_distance`, the field of view is kept
+    ///
+    /// panics if the aperture is negative or the focus distance
+    /// is not positive, or either of them is not finite
+    ///
+    pub fn with_focus(mut self, aperture: f32, focus_distance: f32) -> Self {
+        assert!(
+            aperture >= 0.0 && aperture.is_finite(),
+            "aperture {} is not a non-negative finite number",
+            aperture
+        );
+        assert!(
+            focus_distance > 0.0 && focus_distance.is_finite(),
+            "focus distance {} is not a positive finite number",
+            focus_distance
+        );
+        self.aperture = aperture;
+        self.focus_distance = focus_distance;
+        self.place_viewport();
+        self
+    }
+
+    fn ray_from_lens(&self, u: f32, v: f32, lens_offset: Vector3D) -> Ray {
+        let origin = self.origin + lens_offset;
+        let dir =
+            self.vp_lower_left_corner + u * self.vp_horizontal_span + v * self.vp_vertical_span
+                - origin;
+        Ray::new(origin, dir)
+    }
+}
+
+impl Camera for FovCamera {
+    /// ray through the lens center
+    ///
+    fn ray_from_uv(&self, u: f32, v: f32) -> Ray {
+        self.ray_from_lens(u, v, Vector3D::zero())
+    }
+
+    fn sample_ray(&self, u: f32, v: f32, rng: &mut dyn RngCore) -> Ray {
+        let lens_offset = if self.aperture > 0.0 {
+            let disk = Vector3D::unit_disk_sample(rng) * (self.aperture / 2.0);
+            self.u * disk.x + self.v * disk.y
+        } else {
+            Vector3D::zero()
+        };
+        self.ray_from_lens(u, v, lens_offset)
+    }
+}
+
+/// orthographic camera with parallel rays, objects keep their size
+/// regardless of the distance which suits technical drawings
+///
+pub struct OrthographicCamera {
+    /// world space height of the visible area
+    pub view_height: f32,
+    pub aspect_ratio: f32,
+    direction: Vector3D,
+    vp_lower_left_corner: Vector3D,
+    vp_horizontal_span: Vector3D,
+    vp_vertical_span: Vector3D,
+}
 
+impl OrthographicCamera {
+    pub fn new(
+        origin: Vector3D,
+        lookat: Vector3D,
+        vup: Vector3D,
+        view_height: f32,
+        aspect_ratio: f32,
+    ) -> Self {
+        let (u, v, w) = camera_basis(&origin, &lookat, &vup);
+        let viewport = Viewport::new(view_height, aspect_ratio);
         let vp_horizontal_span = viewport.width * u;
         let vp_vertical_span = viewport.height * v;
-        let vp_lower_left_corner = origin - vp_horizontal_span / 2.0 - vp_vertical_span / 2.0 - w;
-
         Self {
-            origin,
-            vp_lower_left_corner,
+            view_height,
+            aspect_ratio,
+            direction: -w,
+            vp_lower_left_corner: origin - vp_horizontal_span / 2.0 - vp_vertical_span / 2.0,
             vp_horizontal_span,
             vp_vertical_span,
-            vfow,
+        }
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn ray_from_uv(&self, u: f32, v: f32) -> Ray {
+        let origin =
+            self.vp_lower_left_corner + u * self.vp_horizontal_span + v * self.vp_vertical_span;
+        Ray::new(origin, self.direction)
+    }
+}
+
+/// 360 by 180 degrees equirectangular panorama camera, u goes around
+/// the vertical axis and v from the bottom to the top, the image center
+/// looks at `lookat`, images are expected to have 2:1 aspect ratio
+///
+pub struct PanoramaCamera {
+    origin: Vector3D,
+    u: Vector3D,
+    v: Vector3D,
+    w: Vector3D,
+}
+
+impl PanoramaCamera {
+    pub fn new(origin: Vector3D, lookat: Vector3D, vup: Vector3D) -> Self {
+        let (u, v, w) = camera_basis(&origin, &lookat, &vup);
+        Self { origin, u, v, w }
+    }
+}
+
+impl Camera for PanoramaCamera {
+    fn ray_from_uv(&self, u: f32, v: f32) -> Ray {
+        let (sin_phi, cos_phi) = ((u - 0.5) * 2.0 * PI).sin_cos();
+        let (sin_theta, cos_theta) = ((v - 0.5) * PI).sin_cos();
+        let horizontal = sin_phi * self.u - cos_phi * self.w;
+        Ray::new(self.origin, cos_theta * horizontal + sin_theta * self.v)
+    }
+}
+
+/// equidistant fisheye camera, the angle from the view direction grows
+/// linearly with the distance from the image center reaching half of `fov`
+/// at the border of the image circle inscribed into the image height,
+/// corners outside of the circle look beyond the field of view
+///
+pub struct FisheyeCamera {
+    /// field of view of the image circle in degrees, up to 360
+    pub fov: f32,
+    pub aspect_ratio: f32,
+    origin: Vector3D,
+    u: Vector3D,
+    v: Vector3D,
+    w: Vector3D,
+}
+
+impl FisheyeCamera {
+    pub fn new(
+        origin: Vector3D,
+        lookat: Vector3D,
+        vup: Vector3D,
+        fov: f32,
+        aspect_ratio: f32,
+    ) -> Self {
+        let (u, v, w) = camera_basis(&origin, &lookat, &vup);
+        Self {
+            fov,
             aspect_ratio,
+            origin,
+            u,
+            v,
+            w,
         }
     }
 }
 
-impl Camera for FovCamera {
+impl Camera for FisheyeCamera {
     fn ray_from_uv(&self, u: f32, v: f32) -> Ray {
-        let dir =
-            self.vp_lower_left_corner + u * self.vp_horizontal_span + v * self.vp_vertical_span
-                - self.origin;
-        Ray::new(self.origin, dir)
+        // image coordinates with the unit image circle
+        let x = (2.0 * u - 1.0) * self.aspect_ratio;
+        let y = 2.0 * v - 1.0;
+        let r = (x * x + y * y).sqrt();
+        if r < 1e-8 {
+            return Ray::new(self.origin, -self.w);
+        }
+        let theta = (r * self.fov.to_radians() / 2.0).min(PI);
+        let radial = (x / r) * self.u + (y / r) * self.v;
+        Ray::new(self.origin, theta.sin() * radial - theta.cos() * self.w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    #[test]
+    fn test_shutter_ray_times() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let camera = FovCamera::new(
+            Vector3D::zero(),
+            -Vector3D::unit_z(),
+            Vector3D::unit_y(),
+            90.0,
+            1.0,
+        );
+        assert_almost_eq(camera.sample_ray(0.5, 0.5, &mut rng).time, 0.0);
+
+        let camera = camera
+            .with_aspect_ratio(2.0)
+            .with_shutter(Shutter::new(1.0, 2.0));
+        let times: Vec<f32> = (0..100)
+            .map(|_| camera.sample_ray(0.5, 0.5, &mut rng).time)
+            .collect();
+        assert!(times.iter().all(|t| (1.0..2.0).contains(t)));
+        assert!(times.iter().any(|&t| t < 1.5) && times.iter().any(|&t| t > 1.5));
+
+        let ray = camera.sample_ray(0.5, 0.5, &mut rng);
+        assert_vec_eq(&ray.direction, &-Vector3D::unit_z());
+    }
+
+    #[test]
+    fn test_cameras_as_trait_objects() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let origin = Vector3D::zero();
+        let lookat = -Vector3D::unit_z();
+        let vup = Vector3D::unit_y();
+        let cameras: Vec<Box<dyn Camera>> = vec![
+            Box::new(FovCamera::new(origin, lookat, vup, 90.0, 1.0)),
+            Box::new(OrthographicCamera::new(origin, lookat, vup, 2.0, 1.0)),
+            Box::new(PanoramaCamera::new(origin, lookat, vup).with_shutter(Shutter::new(1.0, 2.0))),
+            Box::new(FisheyeCamera::new(origin, lookat, vup, 180.0, 1.0)),
+        ];
+        for camera in &cameras {
+            let ray = camera.sample_ray(0.5, 0.5, &mut rng);
+            assert_vec_eq(&ray.direction.unit(), &-Vector3D::unit_z());
+        }
+        assert!((1.0..2.0).contains(&cameras[2].sample_ray(0.5, 0.5, &mut rng).time));
+    }
+
+    #[test]
+    fn test_thin_lens_focus() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let pinhole = FovCamera::new(
+            Vector3D::zero(),
+            -Vector3D::unit_z(),
+            Vector3D::unit_y(),
+            60.0,
+            1.5,
+        );
+        let lens = FovCamera::new(
+            Vector3D::zero(),
+            -Vector3D::unit_z(),
+            Vector3D::unit_y(),
+            60.0,
+            1.5,
+        )
+        .with_focus(0.5, 4.0)
+        .with_aspect_ratio(1.5);
+
+        // all lens rays through a pixel meet at the focus plane
+        // where the pinhole ray through the same pixel goes
+        let center = pinhole.ray_from_uv(0.2, 0.7);
+        let focus_point = center.at(4.0 / -center.direction.z);
+        for _ in 0..20 {
+            let ray = lens.sample_ray(0.2, 0.7, &mut rng);
+            assert!(ray.origin.z.abs() < 1e-6 && ray.origin.norm() <= 0.25 + 1e-6);
+            let t = (focus_point.z - ray.origin.z) / ray.direction.z;
+            let p = ray.at(t);
+            assert!((p - focus_point).norm() < 1e-4);
+        }
+
+        // pinhole rays stay the same without aperture
+        let ray = pinhole.sample_ray(0.2, 0.7, &mut rng);
+        assert_vec_eq(&ray.origin, &Vector3D::zero());
+        assert_vec_eq(&ray.direction, &center.direction);
+    }
+
+    #[test]
+    fn test_refocus_keeps_field_of_view() {
+        let camera = || {
+            FovCamera::new(
+                Vector3D::zero(),
+                -Vector3D::unit_z(),
+                Vector3D::unit_y(),
+                60.0,
+                1.5,
+            )
+        };
+        let once = camera().with_focus(0.1, 3.0);
+        let twice = camera().with_focus(0.2, 7.0).with_focus(0.1, 3.0);
+        assert_almost_eq(twice.aperture(), 0.1);
+        assert_almost_eq(twice.focus_distance(), 3.0);
+        for (u, v) in [(0.0, 0.0), (0.2, 0.7), (1.0, 1.0)] {
+            let expected = camera().ray_from_uv(u, v).direction.unit();
+            assert_vec_eq(&once.ray_from_uv(u, v).direction.unit(), &expected);
+            assert_vec_eq(&twice.ray_from_uv(u, v).direction.unit(), &expected);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "focus distance")]
+    fn test_focus_at_zero_distance() {
+        FovCamera::new(
+            Vector3D::zero(),
+            -Vector3D::unit_z(),
+            Vector3D::unit_y(),
+            60.0,
+            1.5,
+        )
+        .with_focus(0.1, 0.0);
+    }
+
+    #[test]
+    fn test_orthographic_camera() {
+        let camera = OrthographicCamera::new(
+            Vector3D::new(0.0, 0.0, 5.0),
+            Vector3D::zero(),
+            Vector3D::unit_y(),
+            2.0,
+            2.0,
+        );
+        let center = camera.ray_from_uv(0.5, 0.5);
+        assert_vec_eq(&center.origin, &Vector3D::new(0.0, 0.0, 5.0));
+        assert_vec_eq(&center.direction, &-Vector3D::unit_z());
+
+        let corner = camera.ray_from_uv(1.0, 0.0);
+        assert_vec_eq(&corner.origin, &Vector3D::new(2.0, -1.0, 5.0));
+        assert_vec_eq(&corner.direction, &-Vector3D::unit_z());
+    }
+
+    #[test]
+    fn test_panorama_camera() {
+        let camera = PanoramaCamera::new(Vector3D::zero(), -Vector3D::unit_z(), Vector3D::unit_y());
+        let cases = [
+            ((0.5, 0.5), -Vector3D::unit_z()),
+            ((0.75, 0.5), Vector3D::unit_x()),
+            ((0.25, 0.5), -Vector3D::unit_x()),
+            ((0.0, 0.5), Vector3D::unit_z()),
+            ((0.5, 1.0), Vector3D::unit_y()),
+            ((0.3, 0.0), -Vector3D::unit_y()),
+        ];
+        for ((u, v), direction) in cases {
+            let ray = camera.ray_from_uv(u, v);
+            assert!((ray.direction - direction).norm() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_fisheye_camera() {
+        let camera = FisheyeCamera::new(
+            Vector3D::zero(),
+            -Vector3D::unit_z(),
+            Vector3D::unit_y(),
+            180.0,
+            1.5,
+        );
+        let center = camera.ray_from_uv(0.5, 0.5);
+        assert_vec_eq(&center.direction, &-Vector3D::unit_z());
+
+        // border of the image circle looks 90 degrees to the side
+        let top = camera.ray_from_uv(0.5, 1.0);
+        assert!((top.direction - Vector3D::unit_y()).norm() < 1e-5);
+        let right = camera.ray_from_uv(0.5 + 0.5 / 1.5, 0.5);
+        assert!((right.direction - Vector3D::unit_x()).norm() < 1e-5);
+
+        // half way to the border is 45 degrees
+        let ray = camera.ray_from_uv(0.5, 0.75);
+        assert!((ray.direction - Vector3D::new(0.0, 1.0, -1.0).unit()).norm() < 1e-5);
     }
 }
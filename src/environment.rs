@@ -0,0 +1,162 @@
+use crate::prelude::*;
+use std::f32::consts::PI;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Light coming from infinitely far away, it colors
+/// the rays that escape the scene without hitting anything
+///
+#[derive(Clone)]
+pub enum Environment {
+    /// constant color, black makes emissive materials the only light source
+    Solid(ColorRGB),
+    /// vertical gradient between the bottom and the top colors
+    Gradient { bottom: ColorRGB, top: ColorRGB },
+    /// equirectangular environment map
+    Map(Arc<EnvironmentMap>),
+}
+
+impl Environment {
+    /// white to light blue gradient sky
+    ///
+    pub fn sky() -> Self {
+        Environment::Gradient {
+            bottom: ColorRGB::new(1.0, 1.0, 1.0),
+            top: ColorRGB::new(0.5, 0.7, 1.0),
+        }
+    }
+
+    /// incoming light in the direction of the ray
+    ///
+    pub fn color(&self, ray: &Ray) -> ColorRGB {
+        match self {
+            Environment::Solid(color) => *color,
+            Environment::Gradient { bottom, top } => {
+                let k = 0.5 * (ray.direction.y + 1.0);
+                *bottom * (1.0 - k) + *top * k
+            }
+            Environment::Map(map) => map.sample(&ray.direction),
+        }
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Environment::sky()
+    }
+}
+
+/// Equirectangular (latitude-longitude) environment map with
+/// +Y up, the center of the image looks towards -Z
+///
+pub struct EnvironmentMap {
+    width: u32,
+    height: u32,
+    pixels: Vec<ColorRGB>,
+}
+
+impl EnvironmentMap {
+    /// load HDR or any other image format supported by the `image` crate,
+    /// 8 bit images are decoded from sRGB to linear radiance
+    ///
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, image::ImageError> {
+        Ok(Self::from_image(image::open(path)?))
+    }
+
+    pub fn from_image(image: image::DynamicImage) -> Self {
+        let (width, height, pixels) = crate::texture::linear_pixels(image);
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn dims(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn at(&self, i: u32, j: u32) -> ColorRGB {
+        self.pixels[(j * self.width + i) as usize]
+    }
+
+    /// bilinearly interpolated radiance coming from the direction,
+    /// direction is assumed to be unit
+    ///
+    pub fn sample(&self, direction: &Vector3D) -> ColorRGB {
+        let phi = direction.x.atan2(-direction.z);
+        let theta = direction.y.clamp(-1.0, 1.0).acos();
+
+        // continuous pixel coordinates with pixel centers at integers
+        let x = (0.5 + phi / (2.0 * PI)) * self.width as f32 - 0.5;
+        let y = (theta / PI) * self.height as f32 - 0.5;
+
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let tx = x - x0;
+        let ty = y - y0;
+
+        // wrap around horizontally, clamp vertically
+        let wrap = |i: f32| (i as i64).rem_euclid(self.width as i64) as u32;
+        let clamp = |j: f32| (j.max(0.0) as u32).min(self.height - 1);
+        let (i0, i1) = (wrap(x0), wrap(x0 + 1.0));
+        let (j0, j1) = (clamp(y0), clamp(y0 + 1.0));
+
+        let top = self.at(i0, j0) * (1.0 - tx) + self.at(i1, j0) * tx;
+        let bottom = self.at(i0, j1) * (1.0 - tx) + self.at(i1, j1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gradient_environment() {
+        let env = Environment::Gradient {
+            bottom: ColorRGB::new(1.0, 0.0, 0.0),
+            top: ColorRGB::new(0.0, 0.0, 1.0),
+        };
+        let up = Ray::new(Vector3D::zero(), Vector3D::unit_y());
+        let down = Ray::new(Vector3D::zero(), -Vector3D::unit_y());
+        let side = Ray::new(Vector3D::zero(), Vector3D::unit_x());
+        assert_vec_eq(&env.color(&up), &ColorRGB::new(0.0, 0.0, 1.0));
+        assert_vec_eq(&env.color(&down), &ColorRGB::new(1.0, 0.0, 0.0));
+        assert_vec_eq(&env.color(&side), &ColorRGB::new(0.5, 0.0, 0.5));
+    }
+
+    #[test]
+    fn test_environment_map_sample() {
+        // upper half is red, lower half is green
+        let image = image::Rgb32FImage::from_fn(8, 4, |_, j| {
+            if j < 2 {
+                image::Rgb([2.0, 0.0, 0.0])
+            } else {
+                image::Rgb([0.0, 3.0, 0.0])
+            }
+        });
+        let map = EnvironmentMap::from_image(image::DynamicImage::ImageRgb32F(image));
+        assert_eq!(map.dims(), (8, 4));
+        assert_vec_eq(
+            &map.sample(&Vector3D::unit_y()),
+            &ColorRGB::new(2.0, 0.0, 0.0),
+        );
+        assert_vec_eq(
+            &map.sample(&-Vector3D::unit_y()),
+            &ColorRGB::new(0.0, 3.0, 0.0),
+        );
+        let horizon = map.sample(&Vector3D::unit_x());
+        assert_vec_eq(&horizon, &ColorRGB::new(1.0, 1.5, 0.0));
+    }
+
+    #[test]
+    fn test_environment_map_ldr_to_linear() {
+        let image = image::RgbImage::from_pixel(4, 2, image::Rgb([255, 0, 0]));
+        let map = EnvironmentMap::from_image(image::DynamicImage::ImageRgb8(image));
+        assert_vec_eq(
+            &map.sample(&-Vector3D::unit_z()),
+            &ColorRGB::new(1.0, 0.0, 0.0),
+        );
+    }
+}
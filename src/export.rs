@@ -0,0 +1,533 @@
+use crate::prelude::*;
+use exr::prelude::{
+    AnyChannel, AnyChannels, Encoding, FlatSamples, ImageAttributes, IntegerBounds, Layer,
+    LayerAttributes, SmallVec, WritableImage,
+};
+use image::codecs::bmp::BmpEncoder;
+use image::codecs::hdr::HdrEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::pnm::{PnmEncoder, PnmSubtype, SampleEncoding};
+use image::{ColorType, ImageEncoder, Rgb};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Cursor, Write};
+use std::path::{Path, PathBuf};
+
+/// Errors that can occur while writing an image
+///
+#[derive(Debug)]
+pub enum ExportError {
+    /// output could not be created or written
+    Io(io::Error),
+    /// encoder rejected the image
+    Encoding(image::ImageError),
+    /// OpenEXR encoder rejected the image
+    Exr(exr::error::Error),
+    /// format can not be guessed from the path extension
+    UnknownFormat(PathBuf),
+    /// OpenEXR layer has a different size than the first layer
+    LayerSize(String),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Io(e) => write!(f, "unable to write image: {}", e),
+            ExportError::Encoding(e) => write!(f, "unable to encode image: {}", e),
+            ExportError::Exr(e) => write!(f, "unable to encode OpenEXR image: {}", e),
+            ExportError::UnknownFormat(path) => write!(
+                f,
+                "unknown image format of {}, expected one of {}",
+                path.display(),
+                ExportFormat::EXTENSIONS.join(", ")
+            ),
+            ExportError::LayerSize(name) => {
+                write!(f, "layer `{}` differs in size from the first layer", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExportError::Io(e) => Some(e),
+            ExportError::Encoding(e) => Some(e),
+            ExportError::Exr(e) => Some(e),
+            ExportError::UnknownFormat(_) | ExportError::LayerSize(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for ExportError {
+    fn from(e: io::Error) -> Self {
+        ExportError::Io(e)
+    }
+}
+
+impl From<exr::error::Error> for ExportError {
+    fn from(e: exr::error::Error) -> Self {
+        match e {
+            exr::error::Error::Io(e) => ExportError::Io(e),
+            e => ExportError::Exr(e),
+        }
+    }
+}
+
+impl From<image::ImageError> for ExportError {
+    fn from(e: image::ImageError) -> Self {
+        match e {
+            image::ImageError::IoError(e) => ExportError::Io(e),
+            e => ExportError::Encoding(e),
+        }
+    }
+}
+
+/// File formats the image can be written in, the 8 bit formats
+/// go through the image tone mapping, the float ones keep the linear radiance
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExportFormat {
+    Png,
+    /// binary portable pixmap, P6
+    Ppm,
+    /// plain text portable pixmap, P3
+    PpmAscii,
+    Bmp,
+    /// lossy compression with quality from 1 to 100
+    Jpeg(u8),
+    /// Radiance RGBE, negative values are clipped to zero
+    Hdr,
+    /// portable float map, 32 bit float RGB
+    Pfm,
+    /// OpenEXR with a single RGB layer of 32 bit floats
+    Exr,
+}
+
+impl ExportFormat {
+    /// quality of JPEG images when the format comes from the extension
+    pub const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+    const EXTENSIONS: [&'static str; 8] = ["png", "ppm", "bmp", "jpg", "jpeg", "hdr", "pfm", "exr"];
+
+    /// format of the case insensitive file extension, `ppm` is the binary
+    /// variant as the plain text one has the same extension
+    ///
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(ExportFormat::Png),
+            "ppm" => Some(ExportFormat::Ppm),
+            "bmp" => Some(ExportFormat::Bmp),
+            "jpg" | "jpeg" => Some(ExportFormat::Jpeg(Self::DEFAULT_JPEG_QUALITY)),
+            "hdr" => Some(ExportFormat::Hdr),
+            "pfm" => Some(ExportFormat::Pfm),
+            "exr" => Some(ExportFormat::Exr),
+            _ => None,
+        }
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, ExportError> {
+        let path = path.as_ref();
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Self::from_extension)
+            .ok_or_else(|| ExportError::UnknownFormat(path.to_path_buf()))
+    }
+}
+
+impl Image {
+    /// encode the image to the writer, top row first
+    ///
+    pub fn write_to<W: Write>(
+        &self,
+        mut writer: W,
+        format: ExportFormat,
+    ) -> Result<(), ExportError> {
+        let (width, height) = self.dims();
+        match format {
+            ExportFormat::Png => PngEncoder::new(&mut writer).write_image(
+                &self.as_bytes(),
+                width,
+                height,
+                ColorType::Rgb8,
+            )?,
+            ExportFormat::Ppm | ExportFormat::PpmAscii => {
+                let encoding = match format {
+                    ExportFormat::PpmAscii => SampleEncoding::Ascii,
+                    _ => SampleEncoding::Binary,
+                };
+                PnmEncoder::new(&mut writer)
+                    .with_subtype(PnmSubtype::Pixmap(encoding))
+                    .write_image(&self.as_bytes(), width, height, ColorType::Rgb8)?
+            }
+            ExportFormat::Bmp => BmpEncoder::new(&mut writer).encode(
+                &self.as_bytes(),
+                width,
+                height,
+                ColorType::Rgb8,
+            )?,
+            ExportFormat::Jpeg(quality) => JpegEncoder::new_with_quality(
+                &mut writer,
+                quality.clamp(1, 100),
+            )
+            .write_image(&self.as_bytes(), width, height, ColorType::Rgb8)?,
+            ExportFormat::Hdr => self.write_hdr(&mut writer)?,
+            ExportFormat::Pfm => self.write_pfm(&mut writer)?,
+            ExportFormat::Exr => write_exr_layers(&[("", self)], &mut writer)?,
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn write_hdr<W: Write>(&self, mut writer: W) -> Result<(), ExportError> {
+        let pixels: Vec<Rgb<f32>> = self
+            .rows_top_down()
+            .flatten()
+            .map(|color| Rgb([color.x, color.y, color.z].map(|c| c.max(0.0))))
+            .collect();
+        let (width, height) = self.dims();
+        HdrEncoder::new(&mut writer).encode(&pixels, width as usize, height as usize)?;
+        Ok(())
+    }
+
+    /// http://www.pauldebevec.com/Research/HDR/PFM/
+    ///
+    fn write_pfm<W: Write>(&self, mut writer: W) -> Result<(), ExportError> {
+        // negative scale marks little endian data, rows go from the bottom up
+        write!(writer, "PF\n{} {}\n-1.0\n", self.width, self.height)?;
+        for j in 0..self.height {
+            for i in 0..self.width {
+                let color = self.at(i, j);
+                for c in [color.x, color.y, color.z] {
+                    writer.write_all(&c.to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// write the image to a file in the format of its extension
+    ///
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ExportError> {
+        let format = ExportFormat::from_path(&path)?;
+        self.save_with_format(path, format)
+    }
+
+    pub fn save_with_format<P: AsRef<Path>>(
+        &self,
+        path: P,
+        format: ExportFormat,
+    ) -> Result<(), ExportError> {
+        let file = File::create(path)?;
+        self.write_to(BufWriter::new(file), format)
+    }
+}
+
+/// Write named images as layers of a single OpenEXR file, the channels of
+/// a layer are prefixed with its name, e.g. `albedo.R`, the layer with
+/// an empty name has plain `R`, `G` and `B` channels
+///
+pub fn write_exr_layers<W: Write>(
+    layers: &[(&str, &Image)],
+    mut writer: W,
+) -> Result<(), ExportError> {
+    let size = match layers.first() {
+        Some((_, image)) => (image.width as usize, image.height as usize),
+        None => return Err(ExportError::LayerSize("no layers".to_string())),
+    };
+    let mut exr_layers = Vec::new();
+    for (name, image) in layers {
+        if (image.width as usize, image.height as usize) != size {
+            return Err(ExportError::LayerSize(name.to_string()));
+        }
+        let channel = |component: fn(&ColorRGB) -> f32| {
+            FlatSamples::F32(image.rows_top_down().flatten().map(component).collect())
+        };
+        let channels = AnyChannels::sort(SmallVec::from_vec(vec![
+            AnyChannel::new("R", channel(|c| c.x)),
+            AnyChannel::new("G", channel(|c| c.y)),
+            AnyChannel::new("B", channel(|c| c.z)),
+        ]));
+        let attributes = match name.is_empty() {
+            true => LayerAttributes::default(),
+            false => LayerAttributes::named(*name),
+        };
+        exr_layers.push(Layer::new(
+            size,
+            attributes,
+            Encoding::FAST_LOSSLESS,
+            channels,
+        ));
+    }
+
+    // the encoder seeks in its output, so it is encoded to memory first
+    let attributes = ImageAttributes::new(IntegerBounds::from_dimensions(size));
+    let mut buffer = Cursor::new(Vec::new());
+    exr::image::Image::from_layers(attributes, exr_layers)
+        .write()
+        .to_buffered(&mut buffer)?;
+    writer.write_all(buffer.get_ref())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// `write_exr_layers` to a file
+///
+pub fn save_exr_layers<P: AsRef<Path>>(
+    path: P,
+    layers: &[(&str, &Image)],
+) -> Result<(), ExportError> {
+    let file = File::create(path)?;
+    write_exr_layers(layers, BufWriter::new(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    /// 3x2 image with a distinct color in every pixel
+    ///
+    fn pattern() -> Image {
+        let mut image = Image::new(3, 2);
+        for j in 0..2 {
+            for i in 0..3 {
+                image.set_at(i, j, ColorRGB::new(i as f32 / 2.0, j as f32, 0.25));
+            }
+        }
+        image
+    }
+
+    fn decode(bytes: &[u8]) -> RgbImage {
+        image::load_from_memory(bytes).unwrap().to_rgb8()
+    }
+
+    /// decoded pixel (x, y) from the top left is the image pixel (i, j) from the bottom left
+    ///
+    fn assert_orientation(image: &Image, decoded: &RgbImage) {
+        assert_eq!(decoded.dimensions(), image.dims());
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let mut single = Image::new(1, 1);
+                single.set_at(0, 0, image.at(x, image.height - 1 - y));
+                assert_eq!(decoded.get_pixel(x, y).0.to_vec(), single.as_bytes());
+            }
+        }
+    }
+
+    #[test]
+    fn test_lossless_orientation() {
+        let image = pattern();
+        for format in [
+            ExportFormat::Png,
+            ExportFormat::Ppm,
+            ExportFormat::PpmAscii,
+            ExportFormat::Bmp,
+        ] {
+            let mut bytes = Vec::new();
+            image.write_to(&mut bytes, format).unwrap();
+            assert_orientation(&image, &decode(&bytes));
+        }
+    }
+
+    #[test]
+    fn test_ppm_headers() {
+        let image = pattern();
+        let mut binary = Vec::new();
+        image.write_to(&mut binary, ExportFormat::Ppm).unwrap();
+        assert!(binary.starts_with(b"P6"));
+        // header followed by the raw top left pixel
+        assert_eq!(&binary[binary.len() - 18..][..3], &[0, 255, 137]);
+
+        let mut ascii = Vec::new();
+        image.write_to(&mut ascii, ExportFormat::PpmAscii).unwrap();
+        assert!(ascii.starts_with(b"P3"));
+    }
+
+    #[test]
+    fn test_jpeg_orientation_and_quality() {
+        // blocks large enough to survive the compression
+        let mut image = Image::new(32, 16);
+        for j in 0..16 {
+            for i in 0..32 {
+                let color = match (i < 16, j < 8) {
+                    (true, true) => ColorRGB::new(1.0, 0.0, 0.0),
+                    (false, true) => ColorRGB::new(0.0, 1.0, 0.0),
+                    (true, false) => ColorRGB::new(0.0, 0.0, 1.0),
+                    (false, false) => ColorRGB::new(1.0, 1.0, 1.0),
+                };
+                image.set_at(i, j, color);
+            }
+        }
+        let mut best = Vec::new();
+        image.write_to(&mut best, ExportFormat::Jpeg(100)).unwrap();
+        let decoded = decode(&best);
+        // top left block is the bottom left of the image buffer
+        for (x, y, expected) in [
+            (4, 4, [0, 0, 255]),
+            (28, 4, [255, 255, 255]),
+            (4, 12, [255, 0, 0]),
+            (28, 12, [0, 255, 0]),
+        ] {
+            let pixel = decoded.get_pixel(x, y).0;
+            for (a, b) in pixel.iter().zip(expected) {
+                assert!(a.abs_diff(b) < 16, "{:?} != {:?}", pixel, expected);
+            }
+        }
+
+        let mut worst = Vec::new();
+        image.write_to(&mut worst, ExportFormat::Jpeg(1)).unwrap();
+        assert!(worst.len() < best.len());
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ExportFormat::from_path("out.png").unwrap(),
+            ExportFormat::Png
+        );
+        assert_eq!(
+            ExportFormat::from_path("out.PPM").unwrap(),
+            ExportFormat::Ppm
+        );
+        assert_eq!(
+            ExportFormat::from_path("a/b.bmp").unwrap(),
+            ExportFormat::Bmp
+        );
+        assert_eq!(
+            ExportFormat::from_path("out.jpg").unwrap(),
+            ExportFormat::Jpeg(ExportFormat::DEFAULT_JPEG_QUALITY)
+        );
+        assert_eq!(
+            ExportFormat::from_path("out.exr").unwrap(),
+            ExportFormat::Exr
+        );
+        assert!(matches!(
+            ExportFormat::from_path("out.tiff"),
+            Err(ExportError::UnknownFormat(_))
+        ));
+        assert!(matches!(
+            ExportFormat::from_path("out"),
+            Err(ExportError::UnknownFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_save_by_extension() {
+        let path = std::env::temp_dir().join(format!("yarrr_export_{}.png", std::process::id()));
+        let image = pattern();
+        image.save(&path).unwrap();
+        let decoded = image::open(&path).unwrap().to_rgb8();
+        std::fs::remove_file(&path).unwrap();
+        assert_orientation(&image, &decoded);
+    }
+
+    /// radiance pattern with values above one and a distinct color in every pixel
+    ///
+    fn radiance_pattern() -> Image {
+        let mut image = Image::new(3, 2);
+        for j in 0..2 {
+            for i in 0..3 {
+                image.set_at(i, j, ColorRGB::new(4.0 * i as f32, 0.5 + j as f32, 100.0));
+            }
+        }
+        image
+    }
+
+    /// decoded float pixels from the top left match the image radiance
+    ///
+    fn assert_radiance(image: &Image, decoded: &image::Rgb32FImage, tolerance: f32) {
+        assert_eq!(decoded.dimensions(), image.dims());
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let color = image.at(x, image.height - 1 - y);
+                let pixel = decoded.get_pixel(x, y).0;
+                for (a, b) in pixel.iter().zip([color.x, color.y, color.z]) {
+                    assert!((a - b).abs() <= tolerance * b, "{:?} != {:?}", pixel, color);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hdr_keeps_radiance() {
+        let image = radiance_pattern();
+        let mut bytes = Vec::new();
+        image.write_to(&mut bytes, ExportFormat::Hdr).unwrap();
+        assert!(bytes.starts_with(b"#?RADIANCE"));
+        // shared exponent keeps about 1% precision
+        let decoder = image::codecs::hdr::HdrDecoder::new(bytes.as_slice()).unwrap();
+        let (width, height) = (decoder.metadata().width, decoder.metadata().height);
+        let pixels = decoder.read_image_hdr().unwrap();
+        let decoded =
+            image::Rgb32FImage::from_fn(width, height, |x, y| pixels[(y * width + x) as usize]);
+        assert_radiance(&image, &decoded, 0.01);
+    }
+
+    #[test]
+    fn test_exr_keeps_radiance() {
+        let image = radiance_pattern();
+        let mut bytes = Vec::new();
+        image.write_to(&mut bytes, ExportFormat::Exr).unwrap();
+        let decoded = image::load_from_memory(&bytes).unwrap().to_rgb32f();
+        assert_radiance(&image, &decoded, 0.0);
+    }
+
+    #[test]
+    fn test_pfm_layout() {
+        let image = radiance_pattern();
+        let mut bytes = Vec::new();
+        image.write_to(&mut bytes, ExportFormat::Pfm).unwrap();
+        let header = b"PF\n3 2\n-1.0\n";
+        assert!(bytes.starts_with(header));
+
+        // little endian floats starting from the bottom left pixel
+        let floats: Vec<f32> = bytes[header.len()..]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(floats.len(), 18);
+        assert_eq!(&floats[0..3], &[0.0, 0.5, 100.0]);
+        assert_eq!(&floats[3..6], &[4.0, 0.5, 100.0]);
+        assert_eq!(&floats[15..18], &[8.0, 1.5, 100.0]);
+    }
+
+    #[test]
+    fn test_exr_layers() {
+        use exr::prelude::{ReadChannels, ReadLayers};
+
+        let beauty = radiance_pattern();
+        let mut albedo = Image::new(3, 2);
+        albedo.set_at(0, 1, ColorRGB::new(0.1, 0.2, 0.3));
+
+        let mut bytes = Vec::new();
+        write_exr_layers(&[("beauty", &beauty), ("albedo", &albedo)], &mut bytes).unwrap();
+        let decoded = exr::prelude::read()
+            .no_deep_data()
+            .largest_resolution_level()
+            .all_channels()
+            .all_layers()
+            .all_attributes()
+            .from_buffered(Cursor::new(bytes))
+            .unwrap();
+
+        let names: Vec<String> = decoded
+            .layer_data
+            .iter()
+            .map(|layer| layer.attributes.layer_name.as_ref().unwrap().to_string())
+            .collect();
+        assert_eq!(names, ["beauty", "albedo"]);
+
+        // channels are sorted B, G, R with the top left pixel first
+        let albedo_red = &decoded.layer_data[1].channel_data.list[2];
+        assert_eq!(albedo_red.name.to_string(), "R");
+        assert_eq!(albedo_red.sample_data.value_by_flat_index(0).to_f32(), 0.1);
+
+        let small = Image::new(2, 2);
+        assert!(matches!(
+            write_exr_layers(&[("beauty", &beauty), ("small", &small)], Vec::new()),
+            Err(ExportError::LayerSize(name)) if name == "small"
+        ));
+    }
+}
@@ -1,5 +1,6 @@
 use crate::prelude::*;
 use rand::distributions::{Distribution, Uniform};
+use rand::Rng;
 
 /// type for a RGB pixel
 ///
@@ -10,6 +11,8 @@ pub type ColorRGB = Vector3D;
 pub struct Image {
     pub width: u32,
     pub height: u32,
+    /// display transform of the 8 bit exports, the buffer stays linear
+    pub tone_mapping: ToneMapping,
     buffer: Vec<ColorRGB>,
 }
 
@@ -19,6 +22,7 @@ impl Image {
         Self {
             width,
             height,
+            tone_mapping: ToneMapping::default(),
             buffer: data,
         }
     }
@@ -55,21 +59,28 @@ impl Image {
     /// get u, v normalized coordinates corresponding to
     /// pixel location i, j with uniformly distributed unit error
     ///
-    pub fn pixel_to_uv_noisy(&self, i: u32, j: u32) -> (f32, f32) {
-        let mut rng = rand::thread_rng();
+    pub fn pixel_to_uv_noisy<R: Rng + ?Sized>(&self, i: u32, j: u32, rng: &mut R) -> (f32, f32) {
         let range = Uniform::from(0.0..1.0);
-        let u = (i as f32 + range.sample(&mut rng)) / (self.width - 1) as f32;
-        let v = (j as f32 + range.sample(&mut rng)) / (self.height - 1) as f32;
+        let u = (i as f32 + range.sample(rng)) / (self.width - 1) as f32;
+        let v = (j as f32 + range.sample(rng)) / (self.height - 1) as f32;
         (u, v)
     }
 
-    /// convert image buffer to the vector of bytes
+    /// rows of linear radiance starting from the top of the image,
+    /// row j = 0 is the bottom of the image
+    ///
+    pub fn rows_top_down(&self) -> impl Iterator<Item = &[ColorRGB]> {
+        self.buffer.chunks(self.width as usize).rev()
+    }
+
+    /// convert image buffer to 8 bit RGB bytes with the image tone mapping
+    /// row by row starting from the top left corner
     ///
     pub fn as_bytes(&self) -> Vec<u8> {
-        self.buffer
-            .iter()
-            .rev()
+        self.rows_top_down()
+            .flatten()
             .flat_map(|color| {
+                let color = self.tone_mapping.apply(*color);
                 [
                     (255.999 * color.x) as u8,
                     (255.999 * color.y) as u8,
@@ -101,4 +112,39 @@ mod tests {
         assert!(approx_eq!(f32, u, 1.0, epsilon = 10e-6));
         assert!(approx_eq!(f32, v, 1.0, epsilon = 10e-6));
     }
+
+    #[test]
+    fn test_as_bytes_orientation() {
+        // 3x2 image with a distinct color in every pixel
+        let mut img = Image::new(3, 2);
+        img.tone_mapping.transfer = TransferFunction::Gamma(2.0);
+        for j in 0..2 {
+            for i in 0..3 {
+                img.set_at(i, j, ColorRGB::new(i as f32 / 4.0, j as f32, 0.0));
+            }
+        }
+        let bytes = img.as_bytes();
+        assert_eq!(bytes.len(), 18);
+        // top left first, then along the top row
+        assert_eq!(&bytes[0..3], &[0, 255, 0]);
+        assert_eq!(&bytes[3..6], &[127, 255, 0]);
+        // bottom right last
+        assert_eq!(&bytes[15..18], &[181, 0, 0]);
+    }
+
+    #[test]
+    fn test_as_bytes_tone_mapping() {
+        let mut img = Image::new(2, 2);
+        img.set_at(0, 0, ColorRGB::new(4.0, -1.0, f32::NAN));
+        img.set_at(1, 0, ColorRGB::new(0.5, 0.5, 0.5));
+        assert_eq!(&img.as_bytes()[6..12], &[255, 0, 0, 188, 188, 188]);
+
+        // the same radiance exported with a different look
+        img.tone_mapping = ToneMapping {
+            exposure: -2.0,
+            curve: ToneCurve::Reinhard,
+            ..Default::default()
+        };
+        assert_eq!(&img.as_bytes()[6..9], &[188, 0, 0]);
+    }
 }
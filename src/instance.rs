@@ -0,0 +1,171 @@
+use crate::prelude::*;
+use rand::RngCore;
+
+/// Body placed in the scene by an affine transformation, rays are moved into
+/// the body space instead of moving the body, wrapping an `Arc` of the body
+/// instances it many times without copying its geometry
+///
+pub struct Transformed<T: Hittable> {
+    object: T,
+    transform: Transform,
+    bbox: Option<Aabb>,
+}
+
+impl<T: Hittable> Transformed<T> {
+    pub fn new(object: T, transform: Transform) -> Self {
+        let bbox = object.bounding_box().map(|b| {
+            // box around all 8 transformed corners
+            let corners = (0..8).map(|i| {
+                let corner = Vector3D::new(
+                    if i & 1 == 0 { b.min.x } else { b.max.x },
+                    if i & 2 == 0 { b.min.y } else { b.max.y },
+                    if i & 4 == 0 { b.min.z } else { b.max.z },
+                );
+                let p = transform.point(&corner);
+                Aabb::new(p, p)
+            });
+            corners.reduce(|a, b| a.union(&b)).unwrap()
+        });
+        Self {
+            object,
+            transform,
+            bbox,
+        }
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+}
+
+impl<T: Hittable> Hittable for Transformed<T> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let inverse = self.transform.inverse();
+        let direction = inverse.vector(&ray.direction);
+        // object space ray direction is normalized, so its t is
+        // scaled by the length of the transformed unit direction
+        let scale = direction.norm();
+        let object_ray = Ray::new(inverse.point(&ray.origin), direction).with_time(ray.time);
+
+        let mut record = self.object.hit(&object_ray, t_min * scale, t_max * scale)?;
+        record.t /= scale;
+        record.point = ray.at(record.t);
+        // sidedness is kept by the inverse transpose, no need to flip again
+        record.normal = self.transform.normal(&record.normal).unit();
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.bbox
+    }
+}
+
+/// lights are sampled in the body space, the densities of the world space
+/// directions include the change of the solid angle by the transformation
+///
+impl<T: Hittable + Light> Light for Transformed<T> {
+    fn sample_direction(&self, origin: &Vector3D, rng: &mut dyn RngCore) -> Vector3D {
+        let inverse = self.transform.inverse();
+        let direction = self.object.sample_direction(&inverse.point(origin), rng);
+        self.transform.vector(&direction).unit()
+    }
+
+    fn pdf(&self, origin: &Vector3D, direction: &Vector3D) -> f32 {
+        let inverse = self.transform.inverse();
+        let object_direction = inverse.vector(direction);
+        let scale = object_direction.norm();
+        let pdf = self
+            .object
+            .pdf(&inverse.point(origin), &(object_direction / scale));
+        // solid angle of unit directions mapped by a linear map A
+        // grows by |det A| / |A d|^3
+        pdf * inverse.matrix().linear_determinant().abs() / (scale * scale * scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+    use std::f32::consts::PI;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_hit_transformed_sphere() {
+        let sphere = Sphere::new(Vector3D::zero(), 1.0, Material::None);
+        let transform = Transform::scale(Vector3D::new(1.0, 2.0, 1.0))
+            .then(&Transform::translate(Vector3D::new(0.0, 0.0, -5.0)));
+        let ellipsoid = Transformed::new(sphere, transform);
+
+        let ray = Ray::new(Vector3D::zero(), -Vector3D::unit_z());
+        let record = ellipsoid.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert!((record.t - 4.0).abs() < 1e-5);
+        assert!(record.is_front_face);
+        assert_vec_eq(&record.normal, &Vector3D::unit_z());
+
+        // top of the ellipsoid is stretched to y = 2
+        let down = Ray::new(Vector3D::new(0.0, 5.0, -5.0), -Vector3D::unit_y());
+        let record = ellipsoid.hit(&down, 0.0, f32::INFINITY).unwrap();
+        assert!((record.t - 3.0).abs() < 1e-5);
+        assert_vec_eq(&record.normal, &Vector3D::unit_y());
+        assert!(ellipsoid.hit(&down, 0.0, 2.0).is_none());
+
+        let bbox = ellipsoid.bounding_box().unwrap();
+        assert_vec_eq(&bbox.min, &Vector3D::new(-1.0, -2.0, -6.0));
+        assert_vec_eq(&bbox.max, &Vector3D::new(1.0, 2.0, -4.0));
+    }
+
+    #[test]
+    fn test_instanced_rotated_rect() {
+        let rect = Arc::new(XyRect::new([-1.0, 1.0], [-0.5, 0.5], 0.0, Material::None));
+        let instances: Vec<_> = [-3.0, 3.0]
+            .into_iter()
+            .map(|x| {
+                let transform = Transform::rotate_y(90.0)
+                    .then(&Transform::translate(Vector3D::new(x, 0.0, 0.0)));
+                Transformed::new(rect.clone(), transform)
+            })
+            .collect();
+
+        // rect facing +Z is rotated to face +X
+        let ray = Ray::new(Vector3D::new(10.0, 0.0, 0.9), -Vector3D::unit_x());
+        let record = instances[1].hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert!((record.t - 7.0).abs() < 1e-5);
+        assert!(record.is_front_face);
+        assert_vec_eq(&record.normal, &Vector3D::unit_x());
+
+        let record = instances[0].hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert!((record.t - 13.0).abs() < 1e-5);
+
+        let miss = Ray::new(Vector3D::new(10.0, 0.9, 0.0), -Vector3D::unit_x());
+        assert!(instances[1].hit(&miss, 0.0, f32::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_transformed_light() {
+        let sphere = Sphere::new(Vector3D::zero(), 1.0, Material::None);
+        let transform = Transform::scale(Vector3D::new(2.0, 0.5, 1.0))
+            .then(&Transform::rotate_z(30.0))
+            .then(&Transform::translate(Vector3D::new(0.0, 0.0, -4.0)));
+        let light = Transformed::new(sphere, transform);
+        let origin = Vector3D::new(0.5, 0.2, 0.0);
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        for _ in 0..100 {
+            let direction = light.sample_direction(&origin, &mut rng);
+            assert!(light
+                .hit(&Ray::new(origin, direction), 1e-4, f32::INFINITY)
+                .is_some());
+            assert!(light.pdf(&origin, &direction) > 0.0);
+        }
+        assert_eq!(light.pdf(&origin, &Vector3D::unit_z()), 0.0);
+
+        // densities of the world directions still integrate to one
+        let count = 200000;
+        let total: f32 = (0..count)
+            .map(|_| light.pdf(&origin, &Vector3D::unit_sphere_sample(&mut rng)))
+            .sum();
+        assert!((4.0 * PI * total / count as f32 - 1.0).abs() < 0.05);
+    }
+}
@@ -1,17 +1,51 @@
+pub mod aabb;
+pub mod adaptive;
 pub mod body;
+pub mod bvh;
 pub mod camera;
+pub mod environment;
+pub mod export;
 pub mod image;
+pub mod instance;
+pub mod light;
 pub mod linalg;
 pub mod material;
+pub mod medium;
+pub mod mesh;
+pub mod microfacet;
+pub mod obj;
+pub mod perlin;
+pub mod planar;
+pub mod progressive;
 pub mod ray;
 pub mod renderer;
+pub mod scene;
+pub mod texture;
+pub mod tonemap;
 
 pub mod prelude {
+    pub use crate::aabb::*;
+    pub use crate::adaptive::*;
     pub use crate::body::*;
+    pub use crate::bvh::*;
     pub use crate::camera::*;
+    pub use crate::environment::*;
+    pub use crate::export::*;
     pub use crate::image::*;
+    pub use crate::instance::*;
+    pub use crate::light::*;
     pub use crate::linalg::*;
     pub use crate::material::*;
+    pub use crate::medium::*;
+    pub use crate::mesh::*;
+    pub use crate::microfacet::*;
+    pub use crate::obj::*;
+    pub use crate::perlin::*;
+    pub use crate::planar::*;
+    pub use crate::progressive::*;
     pub use crate::ray::*;
     pub use crate::renderer::*;
+    pub use crate::scene::*;
+    pub use crate::texture::*;
+    pub use crate::tonemap::*;
 }
@@ -0,0 +1,159 @@
+use crate::prelude::*;
+use rand::{Rng, RngCore};
+use std::sync::Arc;
+
+/// trait for bodies that can be sampled directly as light sources,
+/// sampling them instead of only waiting for random bounces to hit them
+/// makes small lights converge much faster
+/// https://raytracing.github.io/books/RayTracingTheRestOfYourLife.html#samplinglightsdirectly
+///
+/// unlike the materials and cameras the random generator is a trait object,
+/// `LightList` keeps lights of different types as `dyn Light` which rules
+/// out generic methods, `LightList::sample` accepts any generator
+///
+pub trait Light: Send + Sync {
+    /// random unit direction from the origin towards a point of the light
+    ///
+    fn sample_direction(&self, origin: &Vector3D, rng: &mut dyn RngCore) -> Vector3D;
+
+    /// solid angle density of `sample_direction` generating the unit direction,
+    /// zero for directions missing the light
+    ///
+    fn pdf(&self, origin: &Vector3D, direction: &Vector3D) -> f32;
+}
+
+impl<T: Light + ?Sized> Light for Arc<T> {
+    fn sample_direction(&self, origin: &Vector3D, rng: &mut dyn RngCore) -> Vector3D {
+        self.as_ref().sample_direction(origin, rng)
+    }
+
+    fn pdf(&self, origin: &Vector3D, direction: &Vector3D) -> f32 {
+        self.as_ref().pdf(origin, direction)
+    }
+}
+
+/// Collection of lights sampled with equal probability,
+/// scenes keep them next to their bodies with `HittableScene::add_light`
+///
+#[derive(Clone, Default)]
+pub struct LightList {
+    lights: Vec<Arc<dyn Light>>,
+}
+
+impl LightList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, light: impl Light + 'static) {
+        self.lights.push(Arc::new(light));
+    }
+
+    pub fn len(&self) -> usize {
+        self.lights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    /// `sample_direction` with a generic generator that may be unsized
+    ///
+    pub fn sample<R: Rng + ?Sized>(&self, origin: &Vector3D, rng: &mut R) -> Vector3D {
+        // `&mut R` is a sized generator that coerces to `dyn RngCore` even when R is not
+        let mut rng = rng;
+        self.sample_direction(origin, &mut rng)
+    }
+}
+
+impl Light for LightList {
+    fn sample_direction(&self, origin: &Vector3D, rng: &mut dyn RngCore) -> Vector3D {
+        let index = rng.gen_range(0..self.lights.len());
+        self.lights[index].sample_direction(origin, rng)
+    }
+
+    fn pdf(&self, origin: &Vector3D, direction: &Vector3D) -> f32 {
+        if self.lights.is_empty() {
+            return 0.0;
+        }
+        let total: f32 = self
+            .lights
+            .iter()
+            .map(|light| light.pdf(origin, direction))
+            .sum();
+        total / self.lights.len() as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+    use std::f32::consts::PI;
+
+    /// uniform sphere estimate of the pdf integral over all directions
+    ///
+    fn pdf_integral(light: &impl Light, origin: &Vector3D) -> f32 {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let count = 200000;
+        let total: f32 = (0..count)
+            .map(|_| light.pdf(origin, &Vector3D::unit_sphere_sample(&mut rng)))
+            .sum();
+        4.0 * PI * total / count as f32
+    }
+
+    fn assert_samples_hit(light: &(impl Light + Hittable), origin: &Vector3D) {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        for _ in 0..100 {
+            let direction = light.sample_direction(origin, &mut rng);
+            assert!(light
+                .hit(&Ray::new(*origin, direction), 1e-4, f32::INFINITY)
+                .is_some());
+            assert!(light.pdf(origin, &direction) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_sphere_light() {
+        let sphere = Sphere::new(Vector3D::new(0.0, 0.0, -3.0), 1.0, Material::None);
+        let origin = Vector3D::zero();
+        assert_samples_hit(&sphere, &origin);
+        assert!((pdf_integral(&sphere, &origin) - 1.0).abs() < 0.05);
+        assert_eq!(sphere.pdf(&origin, &Vector3D::unit_z()), 0.0);
+
+        // from inside the whole sphere of directions is sampled
+        let inside = Vector3D::new(0.0, 0.5, -3.0);
+        assert_almost_eq(sphere.pdf(&inside, &Vector3D::unit_x()), 1.0 / (4.0 * PI));
+    }
+
+    #[test]
+    fn test_rect_light() {
+        let rect = XzRect::new([-1.0, 1.0], [-2.0, 0.5], 2.0, Material::None);
+        let origin = Vector3D::new(0.3, 0.0, 0.0);
+        assert_samples_hit(&rect, &origin);
+        assert!((pdf_integral(&rect, &origin) - 1.0).abs() < 0.05);
+        assert_eq!(rect.pdf(&origin, &-Vector3D::unit_y()), 0.0);
+    }
+
+    #[test]
+    fn test_light_list() {
+        let mut lights = LightList::new();
+        assert_eq!(lights.pdf(&Vector3D::zero(), &Vector3D::unit_x()), 0.0);
+        lights.add(Sphere::new(
+            Vector3D::new(3.0, 0.0, 0.0),
+            1.0,
+            Material::None,
+        ));
+        lights.add(YzRect::new([-1.0, 1.0], [-1.0, 1.0], -2.0, Material::None));
+        assert_eq!(lights.len(), 2);
+        assert!((pdf_integral(&lights, &Vector3D::zero()) - 1.0).abs() < 0.05);
+
+        let sphere_pdf = Sphere::new(Vector3D::new(3.0, 0.0, 0.0), 1.0, Material::None)
+            .pdf(&Vector3D::zero(), &Vector3D::unit_x());
+        assert_almost_eq(
+            lights.pdf(&Vector3D::zero(), &Vector3D::unit_x()),
+            0.5 * sphere_pdf,
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use derive_more::{Add, AddAssign, Div, Neg, Sub, SubAssign};
 use rand::distributions::{Distribution, Uniform};
+use rand::Rng;
 use std::f32::consts::PI;
 use std::ops;
 
@@ -27,7 +28,7 @@ impl ops::Index<usize> for Vector3D {
     }
 }
 
-impl<'a> ops::Neg for &'a Vector3D {
+impl ops::Neg for &Vector3D {
     type Output = Vector3D;
     fn neg(self) -> Self::Output {
         Vector3D {
@@ -38,7 +39,7 @@ impl<'a> ops::Neg for &'a Vector3D {
     }
 }
 
-impl<'a, 'b> ops::Add<&'b Vector3D> for &'a Vector3D {
+impl<'b> ops::Add<&'b Vector3D> for &Vector3D {
     type Output = Vector3D;
 
     fn add(self, other: &'b Vector3D) -> Vector3D {
@@ -50,7 +51,7 @@ impl<'a, 'b> ops::Add<&'b Vector3D> for &'a Vector3D {
     }
 }
 
-impl<'a> ops::Add<Vector3D> for &'a Vector3D {
+impl ops::Add<Vector3D> for &Vector3D {
     type Output = Vector3D;
 
     fn add(self, other: Vector3D) -> Vector3D {
@@ -62,7 +63,7 @@ impl<'a> ops::Add<Vector3D> for &'a Vector3D {
     }
 }
 
-impl<'a, 'b> ops::Sub<&'b Vector3D> for &'a Vector3D {
+impl<'b> ops::Sub<&'b Vector3D> for &Vector3D {
     type Output = Vector3D;
     fn sub(self, other: &'b Vector3D) -> Self::Output {
         Vector3D {
@@ -73,7 +74,7 @@ impl<'a, 'b> ops::Sub<&'b Vector3D> for &'a Vector3D {
     }
 }
 
-impl<'a> ops::Sub<Vector3D> for &'a Vector3D {
+impl ops::Sub<Vector3D> for &Vector3D {
     type Output = Vector3D;
 
     fn sub(self, other: Vector3D) -> Vector3D {
@@ -97,7 +98,7 @@ impl ops::Mul<f32> for Vector3D {
     }
 }
 
-impl<'a> ops::Mul<f32> for &'a Vector3D {
+impl ops::Mul<f32> for &Vector3D {
     type Output = Vector3D;
     fn mul(self, rhs: f32) -> Self::Output {
         Vector3D {
@@ -148,7 +149,7 @@ impl<'a> ops::Mul<&'a Vector3D> for Vector3D {
     }
 }
 
-impl<'a> ops::Mul<Vector3D> for &'a Vector3D {
+impl ops::Mul<Vector3D> for &Vector3D {
     type Output = Vector3D;
 
     fn mul(self, rhs: Vector3D) -> Self::Output {
@@ -168,6 +169,12 @@ impl ops::MulAssign<f32> for Vector3D {
     }
 }
 
+impl From<[f32; 3]> for Vector3D {
+    fn from(xyz: [f32; 3]) -> Self {
+        Self::new(xyz[0], xyz[1], xyz[2])
+    }
+}
+
 impl Vector3D {
     pub fn new(x: f32, y: f32, z: f32) -> Self {
         Self { x, y, z }
@@ -176,24 +183,22 @@ impl Vector3D {
     /// random vector in a unit cube between
     /// min and max coordinates for each axis
     ///
-    pub fn random(min: f32, max: f32) -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn random<R: Rng + ?Sized>(min: f32, max: f32, rng: &mut R) -> Self {
         let range = Uniform::from(min..max);
 
         Self {
-            x: range.sample(&mut rng),
-            y: range.sample(&mut rng),
-            z: range.sample(&mut rng),
+            x: range.sample(rng),
+            y: range.sample(rng),
+            z: range.sample(rng),
         }
     }
 
-    pub fn unit_sphere_sample() -> Self {
+    pub fn unit_sphere_sample<R: Rng + ?Sized>(rng: &mut R) -> Self {
         // based on https://stats.stackexchange.com/a/7988
-        let mut rng = rand::thread_rng();
         let range = Uniform::from(0.0..1.0);
 
-        let z = 2.0 * range.sample(&mut rng) - 1.0; // sample z between -1 and 1
-        let theta = 2.0 * PI * range.sample(&mut rng) - PI; // sample uniform theta
+        let z = 2.0 * range.sample(rng) - 1.0; // sample z between -1 and 1
+        let theta = 2.0 * PI * range.sample(rng) - PI; // sample uniform theta
         let r = (1.0 - z * z).sqrt();
         let x = r * theta.sin();
         let y = r * theta.cos();
@@ -201,6 +206,31 @@ impl Vector3D {
         Vector3D { x, y, z }
     }
 
+    /// uniformly distributed point in the unit disk in the XY plane
+    ///
+    pub fn unit_disk_sample<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let range = Uniform::from(0.0_f32..1.0);
+
+        let r = range.sample(rng).sqrt();
+        let theta = 2.0 * PI * range.sample(rng);
+        Vector3D {
+            x: r * theta.cos(),
+            y: r * theta.sin(),
+            z: 0.0,
+        }
+    }
+
+    /// unit vector in the hemisphere around the unit normal with density
+    /// proportional to the cosine from the normal, cos / PI
+    ///
+    pub fn cosine_sample<R: Rng + ?Sized>(normal: &Vector3D, rng: &mut R) -> Self {
+        // project uniform disk samples up onto the hemisphere
+        let disk = Self::unit_disk_sample(rng);
+        let (tangent, bitangent) = tangent_frame(normal);
+        let height = (1.0 - disk.x * disk.x - disk.y * disk.y).max(0.0).sqrt();
+        tangent * disk.x + bitangent * disk.y + normal * height
+    }
+
     pub fn norm(&self) -> f32 {
         self.norm_squared().sqrt()
     }
@@ -279,6 +309,19 @@ pub fn refract(vec: &Vector3D, normal: &Vector3D, refraction_index: f32) -> Vect
     r_out_perp + r_out_para
 }
 
+/// two unit vectors perpendicular to the unit normal and to each other,
+/// together with the normal they form a right handed basis
+///
+pub fn tangent_frame(normal: &Vector3D) -> (Vector3D, Vector3D) {
+    let helper = if normal.x.abs() > 0.9 {
+        Vector3D::unit_y()
+    } else {
+        Vector3D::unit_x()
+    };
+    let tangent = helper.cross(normal).unit();
+    (tangent, normal.cross(&tangent))
+}
+
 #[inline]
 /// shlick refraction index approximation for reflectivity that varies with angle
 /// https://raytracing.github.io/books/RayTracingInOneWeekend.html#dielectrics/schlickapproximation
@@ -288,6 +331,276 @@ pub fn shlick_reflectance(cos_theta: f32, refraction_index: f32) -> f32 {
     r0 * r0 + (1.0 - r0 * r0) * (1.0 - cos_theta).powi(5)
 }
 
+/// Row major 4x4 matrix of homogeneous 3D transformations
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Matrix4 {
+    pub m: [[f32; 4]; 4],
+}
+
+impl ops::Mul for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, rhs: Matrix4) -> Self::Output {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Matrix4 { m }
+    }
+}
+
+impl Matrix4 {
+    pub fn identity() -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Self { m }
+    }
+
+    pub fn translation(offset: &Vector3D) -> Self {
+        let mut matrix = Self::identity();
+        for i in 0..3 {
+            matrix.m[i][3] = offset[i];
+        }
+        matrix
+    }
+
+    pub fn scaling(factors: &Vector3D) -> Self {
+        let mut matrix = Self::identity();
+        for i in 0..3 {
+            matrix.m[i][i] = factors[i];
+        }
+        matrix
+    }
+
+    /// counter-clockwise rotation around the axis looking from its positive end,
+    /// axis is assumed to be unit
+    ///
+    pub fn rotation(axis: &Vector3D, degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let (x, y, z) = (axis.x, axis.y, axis.z);
+        let c = 1.0 - cos;
+        Self {
+            m: [
+                [
+                    cos + x * x * c,
+                    x * y * c - z * sin,
+                    x * z * c + y * sin,
+                    0.0,
+                ],
+                [
+                    y * x * c + z * sin,
+                    cos + y * y * c,
+                    y * z * c - x * sin,
+                    0.0,
+                ],
+                [
+                    z * x * c - y * sin,
+                    z * y * c + x * sin,
+                    cos + z * z * c,
+                    0.0,
+                ],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        Self { m }
+    }
+
+    /// Gauss-Jordan elimination with partial pivoting,
+    /// None if the matrix is singular
+    ///
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inv = Self::identity().m;
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+                .unwrap();
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for k in 0..4 {
+                a[col][k] *= scale;
+                inv[col][k] *= scale;
+            }
+            for row in 0..4 {
+                let factor = a[row][col];
+                if row == col || factor == 0.0 {
+                    continue;
+                }
+                for k in 0..4 {
+                    a[row][k] -= factor * a[col][k];
+                    inv[row][k] -= factor * inv[col][k];
+                }
+            }
+        }
+        Some(Self { m: inv })
+    }
+
+    /// determinant of the upper left 3x3 part, the volume
+    /// scale of the transformed directions
+    ///
+    pub fn linear_determinant(&self) -> f32 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// transform a position, affected by translation
+    ///
+    pub fn transform_point(&self, p: &Vector3D) -> Vector3D {
+        let m = &self.m;
+        let row = |i: usize| m[i][0] * p.x + m[i][1] * p.y + m[i][2] * p.z + m[i][3];
+        let w = row(3);
+        let p = Vector3D::new(row(0), row(1), row(2));
+        if w == 1.0 {
+            p
+        } else {
+            p / w
+        }
+    }
+
+    /// transform a direction, not affected by translation
+    ///
+    pub fn transform_vector(&self, v: &Vector3D) -> Vector3D {
+        let m = &self.m;
+        let row = |i: usize| m[i][0] * v.x + m[i][1] * v.y + m[i][2] * v.z;
+        Vector3D::new(row(0), row(1), row(2))
+    }
+}
+
+/// Invertible affine transformation keeping both the matrix and its inverse,
+/// transformations are composed with `then` in the order they are applied
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Self {
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+        }
+    }
+
+    /// None if the matrix is not invertible
+    ///
+    pub fn from_matrix(matrix: Matrix4) -> Option<Self> {
+        Some(Self {
+            matrix,
+            inverse: matrix.inverse()?,
+        })
+    }
+
+    pub fn translate(offset: Vector3D) -> Self {
+        Self {
+            matrix: Matrix4::translation(&offset),
+            inverse: Matrix4::translation(&-offset),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// if any of the factors is zero
+    ///
+    pub fn scale(factors: Vector3D) -> Self {
+        assert!(
+            factors.x != 0.0 && factors.y != 0.0 && factors.z != 0.0,
+            "Transform scale factors must be non-zero"
+        );
+        let inverse = Vector3D::new(1.0 / factors.x, 1.0 / factors.y, 1.0 / factors.z);
+        Self {
+            matrix: Matrix4::scaling(&factors),
+            inverse: Matrix4::scaling(&inverse),
+        }
+    }
+
+    /// rotation around the axis by the angle in degrees,
+    /// axis does not need to be unit
+    ///
+    pub fn rotate(axis: Vector3D, degrees: f32) -> Self {
+        let matrix = Matrix4::rotation(&axis.unit(), degrees);
+        Self {
+            matrix,
+            inverse: matrix.transpose(),
+        }
+    }
+
+    pub fn rotate_x(degrees: f32) -> Self {
+        Self::rotate(Vector3D::unit_x(), degrees)
+    }
+
+    pub fn rotate_y(degrees: f32) -> Self {
+        Self::rotate(Vector3D::unit_y(), degrees)
+    }
+
+    pub fn rotate_z(degrees: f32) -> Self {
+        Self::rotate(Vector3D::unit_z(), degrees)
+    }
+
+    /// transformation applying `self` first and `next` after it
+    ///
+    pub fn then(&self, next: &Transform) -> Self {
+        Self {
+            matrix: next.matrix * self.matrix,
+            inverse: self.inverse * next.inverse,
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn matrix(&self) -> &Matrix4 {
+        &self.matrix
+    }
+
+    pub fn point(&self, p: &Vector3D) -> Vector3D {
+        self.matrix.transform_point(p)
+    }
+
+    pub fn vector(&self, v: &Vector3D) -> Vector3D {
+        self.matrix.transform_vector(v)
+    }
+
+    /// transform a surface normal with the inverse transpose so it stays
+    /// perpendicular to the transformed surface, the result is not unit
+    ///
+    pub fn normal(&self, n: &Vector3D) -> Vector3D {
+        self.inverse.transpose().transform_vector(n)
+    }
+}
+
 // test only utils
 #[cfg(test)]
 pub const MAX_TOL_F32: f32 = 1e-6;
@@ -391,6 +704,23 @@ mod tests {
         assert_vec_eq(&reflected, &Vector3D::new(1.0, 1.0, 0.0));
     }
 
+    #[test]
+    fn test_cosine_sample() {
+        use rand::SeedableRng;
+        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+        let normal = Vector3D::new(1.0, 2.0, -1.0).unit();
+        let count = 10000;
+        let mut mean_cos = 0.0;
+        for _ in 0..count {
+            let sample = Vector3D::cosine_sample(&normal, &mut rng);
+            assert_almost_eq(sample.norm(), 1.0);
+            assert!(sample.dot(&normal) >= 0.0);
+            mean_cos += sample.dot(&normal) / count as f32;
+        }
+        // mean cosine of the cosine weighted hemisphere is 2/3
+        assert!((mean_cos - 2.0 / 3.0).abs() < 0.02);
+    }
+
     #[test]
     fn test_refract_same_medium() {
         let normal = Vector3D::new(0.0, 2.0, 0.0).unit();
@@ -416,4 +746,49 @@ mod tests {
             &Vector3D::new(angle_out.sin(), -angle_out.cos(), 0.0).unit(),
         );
     }
+
+    #[test]
+    fn test_matrix_inverse() {
+        let matrix = Matrix4::translation(&Vector3D::new(1.0, -2.0, 3.0))
+            * Matrix4::rotation(&Vector3D::new(1.0, 1.0, 0.0).unit(), 30.0)
+            * Matrix4::scaling(&Vector3D::new(2.0, 0.5, 4.0));
+        let product = matrix * matrix.inverse().unwrap();
+        for i in 0..4 {
+            for j in 0..4 {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((product.m[i][j] - expected).abs() < 1e-5);
+            }
+        }
+        assert!(Matrix4::scaling(&Vector3D::new(1.0, 0.0, 1.0))
+            .inverse()
+            .is_none());
+    }
+
+    #[test]
+    fn test_transform_compose() {
+        let transform = Transform::scale(Vector3D::new(2.0, 2.0, 2.0))
+            .then(&Transform::rotate_z(90.0))
+            .then(&Transform::translate(Vector3D::new(0.0, 0.0, -1.0)));
+        let p = transform.point(&Vector3D::unit_x());
+        assert_vec_eq(&p, &Vector3D::new(0.0, 2.0, -1.0));
+        assert_vec_eq(&transform.inverse().point(&p), &Vector3D::unit_x());
+        assert_vec_eq(
+            &transform.vector(&Vector3D::unit_y()),
+            &Vector3D::new(-2.0, 0.0, 0.0),
+        );
+
+        let from_matrix = Transform::from_matrix(*transform.matrix()).unwrap();
+        assert_vec_eq(&from_matrix.inverse().point(&p), &Vector3D::unit_x());
+    }
+
+    #[test]
+    fn test_transform_normal() {
+        // squashed along y, a 45 degree slope becomes flatter
+        // and its normal steeper
+        let transform = Transform::scale(Vector3D::new(1.0, 0.5, 1.0));
+        let tangent = transform.vector(&Vector3D::new(1.0, 1.0, 0.0));
+        let normal = transform.normal(&Vector3D::new(-1.0, 1.0, 0.0));
+        assert_almost_eq(tangent.dot(&normal), 0.0);
+        assert_vec_eq(&normal.unit(), &Vector3D::new(-1.0, 2.0, 0.0).unit());
+    }
 }
@@ -0,0 +1,422 @@
+use clap::{Parser, ValueEnum};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use yarrr::prelude::*;
+
+/// Render a scene described by a json job file
+///
+#[derive(Parser)]
+#[command(name = "yarrr", version, about)]
+struct Args {
+    /// json job file with camera, image, scene and renderer settings
+    job: PathBuf,
+
+    /// output image path, the format is taken from its extension
+    #[arg(short, long, default_value = "render.png")]
+    output: PathBuf,
+
+    /// image width, keeps the camera aspect ratio if height is not set
+    #[arg(long)]
+    width: Option<u32>,
+
+    /// image height, keeps the camera aspect ratio if width is not set
+    #[arg(long)]
+    height: Option<u32>,
+
+    /// samples per pixel
+    #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..))]
+    samples: Option<u32>,
+
+    /// fewest samples per pixel of adaptive sampling, enables it
+    #[arg(long, value_name = "SAMPLES")]
+    min_samples: Option<u32>,
+
+    /// most samples per pixel of adaptive sampling, enables it
+    #[arg(long, value_name = "SAMPLES")]
+    max_samples: Option<u32>,
+
+    /// largest relative noise of adaptively sampled pixels, enables adaptive sampling
+    #[arg(long, value_name = "ERROR")]
+    noise_threshold: Option<f32>,
+
+    /// image of the samples taken per pixel, from blue at the fewest allowed samples to red at the most
+    #[arg(long, value_name = "PATH")]
+    heatmap: Option<PathBuf>,
+
+    /// maximum number of ray bounces
+    #[arg(short, long)]
+    depth: Option<u32>,
+
+    /// number of render threads, 0 uses all available cores
+    #[arg(short = 'j', long)]
+    threads: Option<usize>,
+
+    /// seed of the random sampling, renders with the same seed are identical
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// output image format (png, ppm, ppm-ascii, bmp, jpeg, hdr, pfm, exr) overriding the extension
+    #[arg(short, long, value_parser = parse_format)]
+    format: Option<ExportFormat>,
+
+    /// quality of JPEG output from 1 to 100
+    #[arg(short, long, default_value_t = ExportFormat::DEFAULT_JPEG_QUALITY,
+        value_parser = clap::value_parser!(u8).range(1..=100))]
+    quality: u8,
+
+    /// progress reporting, a bar on terminals and a log otherwise
+    #[arg(long, value_enum)]
+    progress: Option<ProgressArg>,
+
+    /// how emitting spheres and rectangles are sampled, overrides the job file
+    #[arg(long, value_enum)]
+    light_sampling: Option<LightSamplingArg>,
+
+    /// exposure adjustment in stops applied before tone mapping, overrides the job file
+    #[arg(long, allow_negative_numbers = true)]
+    exposure: Option<f32>,
+
+    /// curve compressing bright radiance for 8 bit formats, overrides the job file
+    #[arg(long, value_enum)]
+    tone_curve: Option<ToneCurveArg>,
+
+    /// plain gamma encoding instead of the sRGB transfer function
+    #[arg(long)]
+    gamma: Option<f32>,
+
+    /// render the whole image one sample pass at a time
+    #[arg(long)]
+    progressive: bool,
+
+    /// preview image of the running average written during progressive rendering,
+    /// the format is taken from its extension, implies --progressive
+    #[arg(long)]
+    preview: Option<PathBuf>,
+
+    /// write the preview after every N passes
+    #[arg(long, value_name = "N", requires = "preview",
+        value_parser = clap::value_parser!(u32).range(1..))]
+    preview_every: Option<u32>,
+
+    /// write the preview at most once in the given number of seconds, 10 by default
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        requires = "preview",
+        conflicts_with = "preview_every"
+    )]
+    preview_seconds: Option<f32>,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum ToneCurveArg {
+    Clamp,
+    Reinhard,
+    Filmic,
+    Aces,
+}
+
+impl From<ToneCurveArg> for ToneCurve {
+    fn from(arg: ToneCurveArg) -> Self {
+        match arg {
+            ToneCurveArg::Clamp => ToneCurve::Clamp,
+            ToneCurveArg::Reinhard => ToneCurve::Reinhard,
+            ToneCurveArg::Filmic => ToneCurve::Filmic,
+            ToneCurveArg::Aces => ToneCurve::Aces,
+        }
+    }
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum LightSamplingArg {
+    None,
+    Mixture,
+    NextEvent,
+}
+
+impl From<LightSamplingArg> for LightSampling {
+    fn from(arg: LightSamplingArg) -> Self {
+        match arg {
+            LightSamplingArg::None => LightSampling::None,
+            LightSamplingArg::Mixture => LightSampling::Mixture,
+            LightSamplingArg::NextEvent => LightSampling::NextEvent,
+        }
+    }
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum ProgressArg {
+    Bar,
+    Log,
+    None,
+}
+
+impl From<ProgressArg> for ProgressMode {
+    fn from(arg: ProgressArg) -> Self {
+        match arg {
+            ProgressArg::Bar => ProgressMode::Bar,
+            ProgressArg::Log => ProgressMode::Log,
+            ProgressArg::None => ProgressMode::Hidden,
+        }
+    }
+}
+
+fn parse_format(format: &str) -> Result<ExportFormat, String> {
+    match format {
+        "ppm-ascii" => Ok(ExportFormat::PpmAscii),
+        _ => ExportFormat::from_extension(format)
+            .ok_or_else(|| format!("unknown image format `{}`", format)),
+    }
+}
+
+/// JPEG format with the given quality, other formats are kept
+///
+fn with_quality(format: ExportFormat, quality: u8) -> ExportFormat {
+    match format {
+        ExportFormat::Jpeg(_) => ExportFormat::Jpeg(quality),
+        format => format,
+    }
+}
+
+/// format of an output file taken from its extension
+///
+fn format_of(path: &Path, quality: u8) -> Result<ExportFormat, String> {
+    ExportFormat::from_path(path)
+        .map(|format| with_quality(format, quality))
+        .map_err(|e| e.to_string())
+}
+
+/// change the image resolution keeping the camera aspect
+/// ratio unless both width and height are given
+///
+fn resize(job: &mut Job, width: Option<u32>, height: Option<u32>) -> Result<(), String> {
+    let aspect_ratio = job.camera.camera.aspect_ratio;
+    let (width, height) = match (width, height) {
+        (None, None) => return Ok(()),
+        (Some(w), Some(h)) => (w, h),
+        (Some(w), None) => (w, (w as f32 / aspect_ratio).round() as u32),
+        (None, Some(h)) => ((h as f32 * aspect_ratio).round() as u32, h),
+    };
+    if width < 2 || height < 2 {
+        return Err(format!(
+            "image size {}x{} is smaller than 2x2",
+            width, height
+        ));
+    }
+
+    job.image = Image::new(width, height);
+    job.camera.camera = job
+        .camera
+        .camera
+        .with_aspect_ratio(width as f32 / height as f32);
+    Ok(())
+}
+
+/// apply the adaptive sampling overrides on top of the job file settings
+///
+fn adapt(settings: &mut RenderSettings, args: &Args) -> Result<(), String> {
+    if args.min_samples.is_none() && args.max_samples.is_none() && args.noise_threshold.is_none() {
+        return Ok(());
+    }
+    let mut adaptive = settings.adaptive.unwrap_or_default();
+    if let Some(min_samples) = args.min_samples {
+        adaptive.min_samples = min_samples;
+    }
+    if let Some(max_samples) = args.max_samples {
+        adaptive.max_samples = max_samples;
+    }
+    if let Some(threshold) = args.noise_threshold {
+        adaptive.threshold = threshold;
+    }
+    adaptive.validate().map_err(|e| e.to_string())?;
+    settings.adaptive = Some(adaptive);
+    Ok(())
+}
+
+fn run(args: Args) -> Result<(), String> {
+    // fail on unknown output formats before rendering
+    let format = match args.format {
+        Some(format) => with_quality(format, args.quality),
+        None => format_of(&args.output, args.quality)?,
+    };
+    let preview = match &args.preview {
+        Some(path) => {
+            let preview_format = format_of(path, args.quality)?;
+            let interval = match (args.preview_every, args.preview_seconds) {
+                (Some(passes), _) => PreviewInterval::Passes(passes),
+                (None, Some(seconds)) if seconds >= 0.0 => PreviewInterval::Seconds(seconds),
+                (None, Some(seconds)) => {
+                    return Err(format!("preview interval {} is negative", seconds))
+                }
+                (None, None) => PreviewInterval::Seconds(10.0),
+            };
+            Some(PreviewWriter::new(path, preview_format, interval))
+        }
+        None => None,
+    };
+    let heatmap_format = match &args.heatmap {
+        Some(path) => Some(format_of(path, args.quality)?),
+        None => None,
+    };
+    let mut job = Job::from_file(&args.job).map_err(|e| e.to_string())?;
+    resize(&mut job, args.width, args.height)?;
+
+    let Job {
+        camera,
+        mut image,
+        scene,
+        mut settings,
+    } = job;
+    if let Some(samples) = args.samples {
+        settings.samples_per_px = samples;
+    }
+    if let Some(depth) = args.depth {
+        settings.bounce_depth = depth;
+    }
+    if let Some(threads) = args.threads {
+        settings.threads = threads;
+    }
+    adapt(&mut settings, &args)?;
+    if args.seed.is_some() {
+        settings.seed = args.seed;
+    }
+    if let Some(light_sampling) = args.light_sampling {
+        settings.light_sampling = light_sampling.into();
+    }
+    if let Some(exposure) = args.exposure {
+        settings.tone_mapping.exposure = exposure;
+    }
+    if let Some(curve) = args.tone_curve {
+        settings.tone_mapping.curve = curve.into();
+    }
+    if let Some(gamma) = args.gamma {
+        settings.tone_mapping.transfer = TransferFunction::Gamma(gamma);
+    }
+    settings
+        .tone_mapping
+        .validate()
+        .map_err(|e| e.to_string())?;
+    settings.progress = match args.progress {
+        Some(progress) => progress.into(),
+        None if std::io::stderr().is_terminal() => ProgressMode::Bar,
+        None => ProgressMode::Log,
+    };
+
+    let progressive = args.progressive || preview.is_some();
+    if progressive && settings.adaptive.is_some() {
+        return Err("adaptive sampling is not supported in progressive rendering".to_string());
+    }
+    if progressive && args.heatmap.is_some() {
+        return Err("heatmap is not supported in progressive rendering".to_string());
+    }
+
+    // heatmap colors span the sample bounds so that renders are comparable
+    let sample_bounds = match settings.adaptive {
+        Some(adaptive) => (adaptive.min_samples, adaptive.max_samples),
+        None => (settings.samples_per_px, settings.samples_per_px),
+    };
+    let world = scene.into_bvh();
+    let counts = match preview {
+        Some(mut preview) => {
+            let mut error = None;
+            color_image_progressive(&mut image, camera, world, settings, |image, passes| {
+                if let Err(e) = preview.update(image, passes) {
+                    error.get_or_insert(e);
+                }
+            });
+            // a failing preview does not waste the finished render
+            if let Some(e) = error {
+                eprintln!("warning: unable to write preview: {}", e);
+            }
+            None
+        }
+        None if args.progressive => {
+            color_image_progressive(&mut image, camera, world, settings, |_, _| {});
+            None
+        }
+        None => Some(color_image(&mut image, camera, world, settings)),
+    };
+    if let (Some(path), Some(format), Some(counts)) = (&args.heatmap, heatmap_format, counts) {
+        counts
+            .heatmap(sample_bounds.0, sample_bounds.1)
+            .save_with_format(path, format)
+            .map_err(|e| format!("unable to save {}: {}", path.display(), e))?;
+    }
+
+    image
+        .save_with_format(&args.output, format)
+        .map_err(|e| format!("unable to save {}: {}", args.output.display(), e))
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job() -> Job {
+        let json = r#"{
+            "camera": { "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90 },
+            "image": { "width": 300, "height": 200 },
+            "scene": []
+        }"#;
+        Job::from_json(json).unwrap()
+    }
+
+    #[test]
+    fn test_resize_keeps_aspect_ratio() {
+        let mut job = job();
+        resize(&mut job, None, None).unwrap();
+        assert_eq!(job.image.dims(), (300, 200));
+
+        resize(&mut job, Some(150), None).unwrap();
+        assert_eq!(job.image.dims(), (150, 100));
+        assert_eq!(job.camera.camera.aspect_ratio, 1.5);
+
+        resize(&mut job, None, Some(50)).unwrap();
+        assert_eq!(job.image.dims(), (75, 50));
+        assert_eq!(job.camera.camera.aspect_ratio, 1.5);
+    }
+
+    #[test]
+    fn test_resize_changes_aspect_ratio() {
+        let mut job = job();
+        resize(&mut job, Some(100), Some(100)).unwrap();
+        assert_eq!(job.image.dims(), (100, 100));
+        assert_eq!(job.camera.camera.aspect_ratio, 1.0);
+    }
+
+    #[test]
+    fn test_output_quality() {
+        let preview = format_of(Path::new("preview.jpg"), 40).unwrap();
+        assert_eq!(preview, ExportFormat::Jpeg(40));
+        let heatmap = format_of(Path::new("heatmap.png"), 40).unwrap();
+        assert_eq!(heatmap, ExportFormat::Png);
+        assert!(format_of(Path::new("preview.gif"), 40).is_err());
+    }
+
+    #[test]
+    fn test_zero_samples() {
+        assert!(Args::try_parse_from(["yarrr", "job.json", "--samples", "0"]).is_err());
+        let args = Args::try_parse_from(["yarrr", "job.json", "--samples", "1"]).unwrap();
+        assert_eq!(args.samples, Some(1));
+    }
+
+    #[test]
+    fn test_resize_rejects_tiny_images() {
+        let mut job = job();
+        assert!(resize(&mut job, Some(1), Some(100)).is_err());
+        // a height of 2 / 1.5 rounds to 1
+        assert!(resize(&mut job, Some(2), None).is_err());
+        assert_eq!(job.image.dims(), (300, 200));
+    }
+}
@@ -1,18 +1,40 @@
 use crate::prelude::*;
 use rand::Rng;
+use std::f32::consts::PI;
 
 /// Structure describing reflected / refracted ray
 ///
 pub struct HitBounce {
     pub ray: Ray,
+    /// scattering divided by the sampling density of the ray direction
     pub attenuation: ColorRGB,
+    /// solid angle density of sampling the ray direction, None for specular
+    /// bounces concentrated in a single direction that has no density
+    pub pdf: Option<f32>,
 }
 
 /// trait for all materials that can produce a HitBounce
 /// on their body intersection with a ray
 ///
 pub trait Scatter {
-    fn scatter(ray: &Ray, hit: &HitRecord) -> Option<HitBounce>;
+    fn scatter<R: Rng + ?Sized>(ray: &Ray, hit: &HitRecord, rng: &mut R) -> Option<HitBounce>;
+
+    /// light scattered from the unit direction towards the ray origin,
+    /// the reflectance already multiplied by the cosine of the direction,
+    /// zero for specular materials
+    ///
+    fn scattering(ray: &Ray, hit: &HitRecord, direction: &Vector3D) -> ColorRGB;
+
+    /// density of `scatter` sampling the unit direction, zero for specular materials
+    ///
+    fn scattering_pdf(ray: &Ray, hit: &HitRecord, direction: &Vector3D) -> f32;
+}
+
+/// trait for all materials that can emit light
+/// on their own when hit by a ray
+///
+pub trait Emit {
+    fn emitted(hit: &HitRecord) -> ColorRGB;
 }
 
 /// Enumeration of basic mateirials
@@ -20,40 +42,61 @@ pub trait Scatter {
 pub enum Material {
     /// 100% reflects normal with gray color
     None,
-    /// 100% reflects fully diffuse ray with albedo color
-    Lambertan(ColorRGB),
-    /// 100% reflects normal + in a fuzzyway ray with albedo color
-    Metal(ColorRGB, f32),
+    /// 100% reflects fully diffuse ray with albedo texture
+    Lambertan(Texture),
+    /// 100% reflects normal + in a fuzzyway ray with albedo texture
+    Metal(Texture, f32),
     /// depending on the cangle can relfect and refract
     Dielectric(f32),
+    /// emits light of a color scaled by intensity and does not reflect
+    DiffuseLight(ColorRGB, f32),
+    /// scatters uniformly in all directions with albedo texture,
+    /// phase function of participating media
+    Isotropic(Texture),
+    /// physically based metallic-roughness material, see `Microfacet`
+    Pbr {
+        base_color: Texture,
+        metallic: f32,
+        roughness: f32,
+    },
+}
+
+impl Emit for Material {
+    fn emitted(hit: &HitRecord) -> ColorRGB {
+        match hit.material {
+            Material::DiffuseLight(color, intensity) => *color * *intensity,
+            _ => ColorRGB::zero(),
+        }
+    }
 }
 
 impl Scatter for Material {
-    fn scatter(ray: &Ray, hit: &HitRecord) -> Option<HitBounce> {
+    fn scatter<R: Rng + ?Sized>(ray: &Ray, hit: &HitRecord, rng: &mut R) -> Option<HitBounce> {
         match hit.material {
             Material::None => Some(HitBounce {
-                ray: Ray::new(hit.point, hit.normal),
+                ray: Ray::new(hit.point, hit.normal).with_time(ray.time),
                 attenuation: ColorRGB::new(0.5, 0.5, 0.5),
+                pdf: None,
             }),
-            Material::Lambertan(color) => {
-                let mut scatter_dir = hit.normal + Vector3D::random(-1.0, 1.0).unit();
-                if scatter_dir.is_near_zero() {
-                    scatter_dir = hit.normal;
-                }
+            Material::Lambertan(albedo) => {
+                // cosine weighted sampling cancels the cosine of the reflectance
+                let scatter_dir = Vector3D::cosine_sample(&hit.normal, rng);
                 Some(HitBounce {
-                    ray: Ray::new(hit.point, scatter_dir),
-                    attenuation: *color,
+                    ray: Ray::new(hit.point, scatter_dir).with_time(ray.time),
+                    attenuation: albedo.value(hit.u, hit.v, &hit.point),
+                    pdf: Some(scatter_dir.dot(&hit.normal).max(0.0) / PI),
                 })
             }
-            Material::Metal(color, fuzz) => {
+            Material::Metal(albedo, fuzz) => {
                 let reflected_dir = reflect(&ray.direction, &hit.normal);
-                let fuzzy_reflected_dir = reflected_dir + Vector3D::unit_sphere_sample() * *fuzz;
+                let fuzzy_reflected_dir = reflected_dir + Vector3D::unit_sphere_sample(rng) * *fuzz;
                 if fuzzy_reflected_dir.dot(&hit.normal).abs() < 10e-8 {
                     return None;
                 }
                 Some(HitBounce {
-                    ray: Ray::new(hit.point, fuzzy_reflected_dir),
-                    attenuation: *color,
+                    ray: Ray::new(hit.point, fuzzy_reflected_dir).with_time(ray.time),
+                    attenuation: albedo.value(hit.u, hit.v, &hit.point),
+                    pdf: None,
                 })
             }
             Material::Dielectric(refraction_index) => {
@@ -65,8 +108,7 @@ impl Scatter for Material {
                 let cos_theta = (-ray.direction).dot(&hit.normal).min(1.0);
                 let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
                 let cannot_refract = ri * sin_theta > 1.0;
-                let angle_too_steep =
-                    shlick_reflectance(cos_theta, ri) > rand::thread_rng().gen_range(0.0..1.0);
+                let angle_too_steep = shlick_reflectance(cos_theta, ri) > rng.gen_range(0.0..1.0);
 
                 let direction = if cannot_refract || angle_too_steep {
                     reflect(&ray.direction, &hit.normal)
@@ -74,10 +116,80 @@ impl Scatter for Material {
                     refract(&ray.direction, &hit.normal, ri)
                 };
                 Some(HitBounce {
-                    ray: Ray::new(hit.point, direction),
+                    ray: Ray::new(hit.point, direction).with_time(ray.time),
                     attenuation: ColorRGB::new(1.0, 1.0, 1.0),
+                    pdf: None,
                 })
             }
+            Material::DiffuseLight(..) => None,
+            Material::Pbr {
+                base_color,
+                metallic,
+                roughness,
+            } => {
+                let base_color = base_color.value(hit.u, hit.v, &hit.point);
+                let surface = Microfacet::new(base_color, *metallic, *roughness);
+                let v = -ray.direction;
+                let l = surface.sample(&hit.normal, &v, rng)?;
+                let pdf = surface.pdf(&hit.normal, &v, &l);
+                if pdf <= 0.0 {
+                    return None;
+                }
+                Some(HitBounce {
+                    ray: Ray::new(hit.point, l).with_time(ray.time),
+                    attenuation: surface.eval(&hit.normal, &v, &l) * (1.0 / pdf),
+                    pdf: Some(pdf),
+                })
+            }
+            Material::Isotropic(albedo) => Some(HitBounce {
+                ray: Ray::new(hit.point, Vector3D::unit_sphere_sample(rng)).with_time(ray.time),
+                attenuation: albedo.value(hit.u, hit.v, &hit.point),
+                pdf: Some(1.0 / (4.0 * PI)),
+            }),
+        }
+    }
+
+    fn scattering(ray: &Ray, hit: &HitRecord, direction: &Vector3D) -> ColorRGB {
+        match hit.material {
+            Material::Lambertan(albedo) => {
+                albedo.value(hit.u, hit.v, &hit.point) * (direction.dot(&hit.normal).max(0.0) / PI)
+            }
+            Material::Pbr {
+                base_color,
+                metallic,
+                roughness,
+            } => {
+                let base_color = base_color.value(hit.u, hit.v, &hit.point);
+                Microfacet::new(base_color, *metallic, *roughness).eval(
+                    &hit.normal,
+                    &-ray.direction,
+                    direction,
+                )
+            }
+            Material::Isotropic(albedo) => {
+                albedo.value(hit.u, hit.v, &hit.point) * (1.0 / (4.0 * PI))
+            }
+            _ => ColorRGB::zero(),
+        }
+    }
+
+    fn scattering_pdf(ray: &Ray, hit: &HitRecord, direction: &Vector3D) -> f32 {
+        match hit.material {
+            Material::Lambertan(_) => direction.dot(&hit.normal).max(0.0) / PI,
+            Material::Pbr {
+                base_color,
+                metallic,
+                roughness,
+            } => {
+                let base_color = base_color.value(hit.u, hit.v, &hit.point);
+                Microfacet::new(base_color, *metallic, *roughness).pdf(
+                    &hit.normal,
+                    &-ray.direction,
+                    direction,
+                )
+            }
+            Material::Isotropic(_) => 1.0 / (4.0 * PI),
+            _ => 0.0,
         }
     }
 }
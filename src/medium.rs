@@ -0,0 +1,128 @@
+use crate::prelude::*;
+
+/// Uniform random number in (0, 1] derived from the ray, hit testing has
+/// no random generator so the ray acts as the seed, rays are already random
+/// so this keeps renders with the same seed reproducible
+///
+fn ray_random(ray: &Ray) -> f32 {
+    let mut h: u64 = 0x9E37_79B9_7F4A_7C15;
+    for value in [
+        ray.origin.x,
+        ray.origin.y,
+        ray.origin.z,
+        ray.direction.x,
+        ray.direction.y,
+        ray.direction.z,
+        ray.time,
+    ] {
+        // splitmix64 mixing of every component
+        h = (h ^ value.to_bits() as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        h ^= h >> 31;
+    }
+    // 24 random bits, shifted away from zero
+    ((h >> 40) as f32 + 1.0) / (1u64 << 24) as f32
+}
+
+/// Volume of constant density like fog or smoke filling a closed boundary,
+/// a ray passing through it scatters at an exponentially distributed distance
+/// in a random direction given by the isotropic phase material
+/// https://raytracing.github.io/books/RayTracingTheNextWeek.html#volumes
+///
+pub struct ConstantMedium<T: Hittable> {
+    boundary: T,
+    neg_inv_density: f32,
+    phase: Material,
+}
+
+impl<T: Hittable> ConstantMedium<T> {
+    pub fn new(boundary: T, density: f32, albedo: impl Into<Texture>) -> Self {
+        Self {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase: Material::Isotropic(albedo.into()),
+        }
+    }
+}
+
+impl<T: Hittable> Hittable for ConstantMedium<T> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        // entry and exit of the boundary along the whole ray line
+        let enter = self.boundary.hit(ray, f32::NEG_INFINITY, f32::INFINITY)?.t;
+        let exit = self.boundary.hit(ray, enter + 1e-4, f32::INFINITY)?.t;
+
+        let enter = enter.max(t_min).max(0.0);
+        let exit = exit.min(t_max);
+        if enter >= exit {
+            return None;
+        }
+
+        // ray direction is unit so t is the distance
+        let hit_distance = self.neg_inv_density * ray_random(ray).ln();
+        if hit_distance > exit - enter {
+            return None;
+        }
+
+        let t = enter + hit_distance;
+        // normal and face are arbitrary, the phase material ignores them
+        Some(HitRecord::new(
+            ray.at(t),
+            t,
+            Vector3D::unit_x(),
+            &self.phase,
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.boundary.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fog(density: f32) -> ConstantMedium<Sphere> {
+        let boundary = Sphere::new(Vector3D::new(0.0, 0.0, -5.0), 1.0, Material::None);
+        ConstantMedium::new(boundary, density, ColorRGB::new(0.5, 0.5, 0.5))
+    }
+
+    fn ray_through(i: u32) -> Ray {
+        // slightly different rays through the center of the volume
+        let x = i as f32 * 1e-4;
+        Ray::new(Vector3D::new(x, 0.0, 0.0), -Vector3D::unit_z())
+    }
+
+    #[test]
+    fn test_dense_medium_scatters_inside() {
+        let fog = fog(1000.0);
+        for i in 0..100 {
+            let record = fog.hit(&ray_through(i), 1e-3, f32::INFINITY).unwrap();
+            assert!(record.t >= 4.0 && record.t <= 6.0);
+            assert!(matches!(record.material, Material::Isotropic(_)));
+        }
+    }
+
+    #[test]
+    fn test_thin_medium_mostly_passes() {
+        // probability of passing 2 units of density 0.1 is exp(-0.2)
+        let fog = fog(0.1);
+        let hits = (0..1000)
+            .filter(|&i| fog.hit(&ray_through(i), 1e-3, f32::INFINITY).is_some())
+            .count();
+        assert!((100..300).contains(&hits), "{} hits", hits);
+    }
+
+    #[test]
+    fn test_medium_from_inside_and_outside_range() {
+        let fog = fog(1000.0);
+        let inside = Ray::new(Vector3D::new(0.0, 0.0, -5.0), -Vector3D::unit_z());
+        let record = fog.hit(&inside, 1e-3, f32::INFINITY).unwrap();
+        assert!(record.t <= 1.0);
+
+        let miss = Ray::new(Vector3D::new(2.0, 0.0, 0.0), -Vector3D::unit_z());
+        assert!(fog.hit(&miss, 1e-3, f32::INFINITY).is_none());
+        assert!(fog.hit(&ray_through(0), 1e-3, 3.0).is_none());
+    }
+}
@@ -0,0 +1,451 @@
+use crate::prelude::*;
+use std::sync::Arc;
+
+/// Möller–Trumbore ray to triangle intersection, returns the ray parameter t
+/// and barycentric coordinates b1, b2 of the hit point relative to p1 and p2
+/// https://en.wikipedia.org/wiki/M%C3%B6ller%E2%80%93Trumbore_intersection_algorithm
+///
+fn intersect_triangle(
+    ray: &Ray,
+    vertices: [&Vector3D; 3],
+    t_min: f32,
+    t_max: f32,
+) -> Option<(f32, f32, f32)> {
+    let [p0, p1, p2] = vertices;
+    let edge1 = p1 - p0;
+    let edge2 = p2 - p0;
+    let p = ray.direction.cross(&edge2);
+    let det = edge1.dot(&p);
+    // ray is parallel to the triangle plane
+    if det.abs() < 1e-8 {
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    let s = &ray.origin - p0;
+    let b1 = s.dot(&p) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+
+    let q = s.cross(&edge1);
+    let b2 = ray.direction.dot(&q) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(&q) * inv_det;
+    if (t < t_min) | (t > t_max) {
+        return None;
+    }
+    Some((t, b1, b2))
+}
+
+/// Texture coordinates of a triangle corner
+///
+pub type TexCoord = (f32, f32);
+
+/// Build the hit record for a triangle intersection, per vertex normals
+/// are interpolated for smooth shading and flipped to the ray facing side,
+/// per vertex texture coordinates are interpolated too and the barycentric
+/// coordinates serve as the texture coordinates without them
+///
+fn triangle_hit_record<'a>(
+    ray: &Ray,
+    vertices: [&Vector3D; 3],
+    normals: Option<[&Vector3D; 3]>,
+    texcoords: Option<[TexCoord; 3]>,
+    hit: (f32, f32, f32),
+    material: &'a Material,
+) -> HitRecord<'a> {
+    let [p0, p1, p2] = vertices;
+    let (t, b1, b2) = hit;
+    let face_normal = (p1 - p0).cross(&(p2 - p0)).unit();
+    let (u, v) = match texcoords {
+        Some([(u0, v0), (u1, v1), (u2, v2)]) => {
+            let b0 = 1.0 - b1 - b2;
+            (b0 * u0 + b1 * u1 + b2 * u2, b0 * v0 + b1 * v1 + b2 * v2)
+        }
+        None => (b1, b2),
+    };
+    let mut record = HitRecord::new(ray.at(t), t, face_normal, material).with_uv(u, v);
+    record.set_ray_facing_normal(ray);
+
+    if let Some([n0, n1, n2]) = normals {
+        let shading_normal = ((1.0 - b1 - b2) * n0 + b1 * n1 + b2 * n2).unit();
+        record.normal = if shading_normal.dot(&record.normal) < 0.0 {
+            -shading_normal
+        } else {
+            shading_normal
+        };
+    }
+    record
+}
+
+/// Box around the triangle, padded so that the box of an axis
+/// aligned triangle is not infinitely thin
+///
+fn triangle_bounding_box(vertices: [&Vector3D; 3]) -> Aabb {
+    let [p0, p1, p2] = vertices;
+    let pad = Vector3D::new(1e-4, 1e-4, 1e-4);
+    let bbox = Aabb::new(*p0, *p0)
+        .union(&Aabb::new(*p1, *p1))
+        .union(&Aabb::new(*p2, *p2));
+    Aabb::new(bbox.min - pad, bbox.max + pad)
+}
+
+/// Single triangle with a material
+///
+pub struct Triangle {
+    pub vertices: [Vector3D; 3],
+    /// per vertex normals for smooth shading, flat shading if None
+    pub normals: Option<[Vector3D; 3]>,
+    /// per vertex texture coordinates, barycentric coordinates if None
+    pub texcoords: Option<[TexCoord; 3]>,
+    pub material: Material,
+}
+
+impl Triangle {
+    pub fn new(p0: Vector3D, p1: Vector3D, p2: Vector3D, material: Material) -> Self {
+        Self {
+            vertices: [p0, p1, p2],
+            normals: None,
+            texcoords: None,
+            material,
+        }
+    }
+
+    /// same triangle with smooth shading normals, normals are assumed to be unit
+    ///
+    pub fn with_normals(mut self, normals: [Vector3D; 3]) -> Self {
+        self.normals = Some(normals);
+        self
+    }
+
+    /// same triangle with texture coordinates of its vertices
+    ///
+    pub fn with_texcoords(mut self, texcoords: [TexCoord; 3]) -> Self {
+        self.texcoords = Some(texcoords);
+        self
+    }
+
+    fn vertex_refs(&self) -> [&Vector3D; 3] {
+        let [p0, p1, p2] = &self.vertices;
+        [p0, p1, p2]
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let vertices = self.vertex_refs();
+        let hit = intersect_triangle(ray, vertices, t_min, t_max)?;
+        let normals = self.normals.as_ref().map(|[n0, n1, n2]| [n0, n1, n2]);
+        Some(triangle_hit_record(
+            ray,
+            vertices,
+            normals,
+            self.texcoords,
+            hit,
+            &self.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(triangle_bounding_box(self.vertex_refs()))
+    }
+}
+
+/// Vertex and index buffers shared by all faces of a mesh
+///
+struct MeshData {
+    vertices: Vec<Vector3D>,
+    /// per vertex normals or empty for flat shading
+    normals: Vec<Vector3D>,
+    /// per vertex texture coordinates or empty
+    texcoords: Vec<Option<TexCoord>>,
+    indices: Vec<[u32; 3]>,
+    material: Material,
+}
+
+/// Single face of a mesh referring to the shared mesh buffers
+///
+struct MeshFace {
+    mesh: Arc<MeshData>,
+    face: usize,
+}
+
+impl MeshFace {
+    fn vertices(&self) -> [&Vector3D; 3] {
+        let [a, b, c] = self.mesh.indices[self.face];
+        let v = &self.mesh.vertices;
+        [&v[a as usize], &v[b as usize], &v[c as usize]]
+    }
+
+    fn normals(&self) -> Option<[&Vector3D; 3]> {
+        if self.mesh.normals.is_empty() {
+            return None;
+        }
+        let [a, b, c] = self.mesh.indices[self.face];
+        let n = &self.mesh.normals;
+        Some([&n[a as usize], &n[b as usize], &n[c as usize]])
+    }
+
+    /// texture coordinates if all face vertices have them
+    ///
+    fn texcoords(&self) -> Option<[TexCoord; 3]> {
+        if self.mesh.texcoords.is_empty() {
+            return None;
+        }
+        let [a, b, c] = self.mesh.indices[self.face];
+        let uv = &self.mesh.texcoords;
+        Some([uv[a as usize]?, uv[b as usize]?, uv[c as usize]?])
+    }
+}
+
+impl Hittable for MeshFace {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let vertices = self.vertices();
+        let hit = intersect_triangle(ray, vertices, t_min, t_max)?;
+        Some(triangle_hit_record(
+            ray,
+            vertices,
+            self.normals(),
+            self.texcoords(),
+            hit,
+            &self.mesh.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(triangle_bounding_box(self.vertices()))
+    }
+}
+
+/// Triangle mesh with a single material, vertices are shared between
+/// the faces and the faces are organized in a bounding volume hierarchy
+///
+pub struct Mesh {
+    faces: Option<BvhNode<MeshFace>>,
+    face_count: usize,
+}
+
+impl Mesh {
+    /// flat shaded mesh where each face is a triple of indices into `vertices`
+    ///
+    /// # Panics
+    ///
+    /// if any index is out of the `vertices` bounds
+    ///
+    pub fn new(vertices: Vec<Vector3D>, indices: Vec<[u32; 3]>, material: Material) -> Self {
+        Self::with_normals(vertices, Vec::new(), indices, material)
+    }
+
+    /// smooth shaded mesh with unit `normals` for every vertex,
+    /// empty `normals` fall back to flat shading
+    ///
+    /// # Panics
+    ///
+    /// if any index is out of the `vertices` bounds or there
+    /// is not exactly one normal per vertex
+    ///
+    pub fn with_normals(
+        vertices: Vec<Vector3D>,
+        normals: Vec<Vector3D>,
+        indices: Vec<[u32; 3]>,
+        material: Material,
+    ) -> Self {
+        Self::with_texcoords(vertices, normals, Vec::new(), indices, material)
+    }
+
+    /// mesh with optional texture coordinates for every vertex, faces
+    /// with a vertex without them and meshes with empty `texcoords` use
+    /// the barycentric coordinates as the texture coordinates instead
+    ///
+    /// # Panics
+    ///
+    /// if any index is out of the `vertices` bounds or there is not
+    /// exactly one normal or texture coordinate per vertex
+    ///
+    pub fn with_texcoords(
+        vertices: Vec<Vector3D>,
+        normals: Vec<Vector3D>,
+        texcoords: Vec<Option<TexCoord>>,
+        indices: Vec<[u32; 3]>,
+        material: Material,
+    ) -> Self {
+        assert!(
+            indices
+                .iter()
+                .flatten()
+                .all(|&i| (i as usize) < vertices.len()),
+            "Mesh index is out of vertex buffer bounds"
+        );
+        assert!(
+            normals.is_empty() || normals.len() == vertices.len(),
+            "Mesh must have a normal per vertex"
+        );
+        assert!(
+            texcoords.is_empty() || texcoords.len() == vertices.len(),
+            "Mesh must have a texture coordinate per vertex"
+        );
+
+        let face_count = indices.len();
+        let mesh = Arc::new(MeshData {
+            vertices,
+            normals,
+            texcoords,
+            indices,
+            material,
+        });
+        let faces: Vec<MeshFace> = (0..face_count)
+            .map(|face| MeshFace {
+                mesh: mesh.clone(),
+                face,
+            })
+            .collect();
+        Self {
+            faces: (!faces.is_empty()).then(|| BvhNode::new(faces)),
+            face_count,
+        }
+    }
+
+    pub fn face_count(&self) -> usize {
+        self.face_count
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.faces.as_ref()?.hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.faces.as_ref()?.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_triangle() -> Triangle {
+        Triangle::new(
+            Vector3D::new(-1.0, -1.0, -2.0),
+            Vector3D::new(1.0, -1.0, -2.0),
+            Vector3D::new(0.0, 1.0, -2.0),
+            Material::None,
+        )
+    }
+
+    #[test]
+    fn test_hit_triangle_front() {
+        let triangle = make_triangle();
+        let ray = Ray::new(Vector3D::zero(), -Vector3D::unit_z());
+        let record = triangle.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert_almost_eq(record.t, 2.0);
+        assert!(record.is_front_face);
+        assert_vec_eq(&record.point, &Vector3D::new(0.0, 0.0, -2.0));
+        assert_vec_eq(&record.normal, &Vector3D::unit_z());
+    }
+
+    #[test]
+    fn test_hit_triangle_back() {
+        let triangle = make_triangle();
+        let ray = Ray::new(Vector3D::new(0.0, 0.0, -4.0), Vector3D::unit_z());
+        let record = triangle.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert_almost_eq(record.t, 2.0);
+        assert!(!record.is_front_face);
+        assert_vec_eq(&record.normal, &-Vector3D::unit_z());
+    }
+
+    #[test]
+    fn test_hit_triangle_miss() {
+        let triangle = make_triangle();
+        let outside = Ray::new(Vector3D::new(1.0, 1.0, 0.0), -Vector3D::unit_z());
+        assert!(triangle.hit(&outside, 0.0, f32::INFINITY).is_none());
+        let parallel = Ray::new(Vector3D::zero(), Vector3D::unit_x());
+        assert!(triangle.hit(&parallel, 0.0, f32::INFINITY).is_none());
+        let too_far = Ray::new(Vector3D::zero(), -Vector3D::unit_z());
+        assert!(triangle.hit(&too_far, 0.0, 1.0).is_none());
+    }
+
+    #[test]
+    fn test_hit_triangle_smooth_normals() {
+        let n = Vector3D::new(1.0, 0.0, 1.0).unit();
+        let triangle = make_triangle().with_normals([n, n, n]);
+        let ray = Ray::new(Vector3D::zero(), -Vector3D::unit_z());
+        let record = triangle.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert_vec_eq(&record.normal, &n);
+    }
+
+    #[test]
+    fn test_hit_mesh_quad() {
+        let vertices = vec![
+            Vector3D::new(-1.0, -1.0, -3.0),
+            Vector3D::new(1.0, -1.0, -3.0),
+            Vector3D::new(1.0, 1.0, -3.0),
+            Vector3D::new(-1.0, 1.0, -3.0),
+        ];
+        let mesh = Mesh::new(vertices, vec![[0, 1, 2], [0, 2, 3]], Material::None);
+        assert_eq!(mesh.face_count(), 2);
+
+        for origin in [Vector3D::new(0.5, -0.5, 0.0), Vector3D::new(-0.5, 0.5, 0.0)] {
+            let ray = Ray::new(origin, -Vector3D::unit_z());
+            let record = mesh.hit(&ray, 0.0, f32::INFINITY).unwrap();
+            assert_almost_eq(record.t, 3.0);
+            assert_vec_eq(&record.normal, &Vector3D::unit_z());
+        }
+
+        let ray = Ray::new(Vector3D::new(1.5, 0.0, 0.0), -Vector3D::unit_z());
+        assert!(mesh.hit(&ray, 0.0, f32::INFINITY).is_none());
+
+        let bbox = mesh.bounding_box().unwrap();
+        assert!(bbox.min.z < -3.0 && bbox.max.z > -3.0);
+    }
+
+    #[test]
+    fn test_triangle_texcoords() {
+        let ray = Ray::new(Vector3D::zero(), -Vector3D::unit_z());
+        // hit point (0, 0) has barycentric coordinates (0.25, 0.5)
+        let triangle = make_triangle();
+        let record = triangle.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert_almost_eq(record.u, 0.25);
+        assert_almost_eq(record.v, 0.5);
+
+        let triangle = make_triangle().with_texcoords([(0.0, 0.0), (1.0, 0.0), (0.5, 1.0)]);
+        let record = triangle.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert_almost_eq(record.u, 0.5);
+        assert_almost_eq(record.v, 0.5);
+    }
+
+    #[test]
+    fn test_mesh_texcoords() {
+        let vertices = vec![
+            Vector3D::new(-1.0, -1.0, -3.0),
+            Vector3D::new(1.0, -1.0, -3.0),
+            Vector3D::new(1.0, 1.0, -3.0),
+            Vector3D::new(-1.0, 1.0, -3.0),
+            Vector3D::new(-1.0, 3.0, -3.0),
+        ];
+        let texcoords = vec![
+            Some((0.0, 0.0)),
+            Some((1.0, 0.0)),
+            Some((1.0, 1.0)),
+            Some((0.0, 1.0)),
+            None,
+        ];
+        let indices = vec![[0, 1, 2], [0, 2, 3], [3, 2, 4]];
+        let mesh = Mesh::with_texcoords(vertices, Vec::new(), texcoords, indices, Material::None);
+
+        let ray = Ray::new(Vector3D::new(0.5, -0.5, 0.0), -Vector3D::unit_z());
+        let record = mesh.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert_almost_eq(record.u, 0.75);
+        assert_almost_eq(record.v, 0.25);
+
+        // the face with a vertex without texture coordinates falls back to barycentrics
+        let ray = Ray::new(Vector3D::new(-0.5, 1.5, 0.0), -Vector3D::unit_z());
+        let record = mesh.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert_almost_eq(record.u, 0.25);
+        assert_almost_eq(record.v, 0.25);
+    }
+}
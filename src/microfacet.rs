@@ -0,0 +1,193 @@
+use crate::prelude::*;
+use rand::Rng;
+use std::f32::consts::PI;
+
+/// Metallic-roughness surface with a Lambertian diffuse base and a GGX
+/// Cook-Torrance specular layer as used by glTF and most game engines
+/// https://google.github.io/filament/Filament.html#materialsystem/standardmodel
+///
+/// all directions are unit and point away from the surface,
+/// `v` towards the viewer and `l` towards the light
+///
+pub struct Microfacet {
+    base_color: ColorRGB,
+    metallic: f32,
+    /// GGX width, roughness squared
+    alpha: f32,
+}
+
+impl Microfacet {
+    /// roughness is clamped away from zero where GGX becomes a singular mirror
+    ///
+    pub fn new(base_color: ColorRGB, metallic: f32, roughness: f32) -> Self {
+        let roughness = roughness.clamp(0.02, 1.0);
+        Self {
+            base_color,
+            metallic: metallic.clamp(0.0, 1.0),
+            alpha: roughness * roughness,
+        }
+    }
+
+    /// reflectance at normal incidence, 4% for dielectrics
+    /// and the base color for metals
+    ///
+    fn f0(&self) -> ColorRGB {
+        let dielectric = ColorRGB::new(0.04, 0.04, 0.04);
+        dielectric * (1.0 - self.metallic) + self.base_color * self.metallic
+    }
+
+    /// probability of sampling the specular lobe instead of the diffuse one
+    ///
+    fn specular_probability(&self) -> f32 {
+        0.5 * (1.0 + self.metallic)
+    }
+
+    /// GGX normal distribution
+    ///
+    fn distribution(&self, n_dot_h: f32) -> f32 {
+        let a2 = self.alpha * self.alpha;
+        let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+        a2 / (PI * d * d)
+    }
+
+    /// Smith masking of a single direction
+    ///
+    fn masking(&self, n_dot_x: f32) -> f32 {
+        let a2 = self.alpha * self.alpha;
+        2.0 * n_dot_x / (n_dot_x + (a2 + (1.0 - a2) * n_dot_x * n_dot_x).sqrt())
+    }
+
+    fn fresnel(&self, v_dot_h: f32) -> ColorRGB {
+        let f0 = self.f0();
+        let k = (1.0 - v_dot_h).clamp(0.0, 1.0).powi(5);
+        f0 + (ColorRGB::new(1.0, 1.0, 1.0) - f0) * k
+    }
+
+    /// reflected radiance factor, BRDF times the cosine of the light direction
+    ///
+    pub fn eval(&self, n: &Vector3D, v: &Vector3D, l: &Vector3D) -> ColorRGB {
+        let n_dot_l = n.dot(l);
+        let n_dot_v = n.dot(v).max(1e-4);
+        if n_dot_l <= 0.0 {
+            return ColorRGB::zero();
+        }
+        let h = (v + l).unit();
+        let fresnel = self.fresnel(v.dot(&h));
+
+        let specular = fresnel
+            * (self.distribution(n.dot(&h)) * self.masking(n_dot_v) * self.masking(n_dot_l)
+                / (4.0 * n_dot_v * n_dot_l));
+        let diffuse = (ColorRGB::new(1.0, 1.0, 1.0) - fresnel)
+            * self.base_color
+            * ((1.0 - self.metallic) / PI);
+        (diffuse + specular) * n_dot_l
+    }
+
+    /// probability density of `sample` returning the light direction
+    ///
+    pub fn pdf(&self, n: &Vector3D, v: &Vector3D, l: &Vector3D) -> f32 {
+        let n_dot_l = n.dot(l);
+        if n_dot_l <= 0.0 {
+            return 0.0;
+        }
+        let h = (v + l).unit();
+        let specular =
+            self.distribution(n.dot(&h)) * n.dot(&h).max(0.0) / (4.0 * v.dot(&h).abs().max(1e-4));
+        let diffuse = n_dot_l / PI;
+        let p = self.specular_probability();
+        p * specular + (1.0 - p) * diffuse
+    }
+
+    /// light direction importance sampled from either the GGX distribution
+    /// of normals or the cosine weighted hemisphere, None below the surface
+    ///
+    pub fn sample<R: Rng + ?Sized>(
+        &self,
+        n: &Vector3D,
+        v: &Vector3D,
+        rng: &mut R,
+    ) -> Option<Vector3D> {
+        let l = if rng.gen::<f32>() < self.specular_probability() {
+            let (tangent, bitangent) = tangent_frame(n);
+            let (e1, e2): (f32, f32) = (rng.gen(), rng.gen());
+            let phi = 2.0 * PI * e2;
+            let a2 = self.alpha * self.alpha;
+            let cos_theta = ((1.0 - e1) / (1.0 + (a2 - 1.0) * e1)).sqrt();
+            let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+            let h = tangent * (sin_theta * phi.cos())
+                + bitangent * (sin_theta * phi.sin())
+                + n * cos_theta;
+            reflect(&-v, &h)
+        } else {
+            Vector3D::cosine_sample(n, rng)
+        };
+        (n.dot(&l) > 0.0).then_some(l)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    fn mean_reflectance(surface: &Microfacet, v: &Vector3D) -> ColorRGB {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+        let n = Vector3D::unit_z();
+        let count = 20000;
+        let mut total = ColorRGB::zero();
+        for _ in 0..count {
+            if let Some(l) = surface.sample(&n, v, &mut rng) {
+                total += surface.eval(&n, v, &l) * (1.0 / surface.pdf(&n, v, &l));
+            }
+        }
+        total * (1.0 / count as f32)
+    }
+
+    #[test]
+    fn test_energy_is_conserved() {
+        let v = Vector3D::new(0.3, 0.0, 1.0).unit();
+        let white = ColorRGB::new(1.0, 1.0, 1.0);
+        for (metallic, roughness) in [(0.0, 0.2), (0.0, 1.0), (1.0, 0.3)] {
+            let surface = Microfacet::new(white, metallic, roughness);
+            let albedo = mean_reflectance(&surface, &v);
+            assert!(albedo.x > 0.9 && albedo.x < 1.01, "{:?}", albedo);
+        }
+        // single scattering microfacets lose energy on very rough metals
+        let albedo = mean_reflectance(&Microfacet::new(white, 1.0, 1.0), &v);
+        assert!(albedo.x > 0.2 && albedo.x < 1.0, "{:?}", albedo);
+    }
+
+    #[test]
+    fn test_pdf_integrates_below_one() {
+        // uniform hemisphere estimate of the pdf integral, the missing part
+        // are specular samples reflected below the surface
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        let n = Vector3D::unit_z();
+        let v = Vector3D::new(0.5, 0.0, 1.0).unit();
+        let surface = Microfacet::new(ColorRGB::new(0.5, 0.5, 0.5), 0.5, 0.6);
+        let count = 50000;
+        let total: f32 = (0..count)
+            .map(|_| {
+                let mut l = Vector3D::unit_sphere_sample(&mut rng);
+                l.z = l.z.abs();
+                surface.pdf(&n, &v, &l) * 2.0 * PI
+            })
+            .sum();
+        let integral = total / count as f32;
+        assert!(integral > 0.85 && integral < 1.01, "{}", integral);
+    }
+
+    #[test]
+    fn test_smooth_metal_is_mirror_like() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(2);
+        let n = Vector3D::unit_z();
+        let v = Vector3D::new(1.0, 0.0, 1.0).unit();
+        let mirror = reflect(&-v, &n);
+        let surface = Microfacet::new(ColorRGB::new(0.9, 0.6, 0.3), 1.0, 0.0);
+        for _ in 0..100 {
+            let l = surface.sample(&n, &v, &mut rng).unwrap();
+            assert!(l.dot(&mirror) > 0.99);
+        }
+    }
+}
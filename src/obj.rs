@@ -0,0 +1,624 @@
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// Errors that can occur while loading a Wavefront OBJ model
+///
+#[derive(Debug)]
+pub enum ObjError {
+    /// model or material library file could not be read
+    Io(std::io::Error),
+    /// malformed statement at the line (1 based)
+    Parse { line: usize, message: String },
+    /// `usemtl` refers to a material that is not in the material library
+    UnknownMaterial(String),
+    /// placement scale is zero or not finite
+    InvalidScale(f32),
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(e) => write!(f, "unable to read model: {}", e),
+            ObjError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            ObjError::UnknownMaterial(name) => write!(f, "unknown material `{}`", name),
+            ObjError::InvalidScale(scale) => {
+                write!(f, "scale {} is not a non-zero finite number", scale)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ObjError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ObjError {
+    fn from(e: std::io::Error) -> Self {
+        ObjError::Io(e)
+    }
+}
+
+fn parse_error(line: usize, message: impl Into<String>) -> ObjError {
+    ObjError::Parse {
+        line,
+        message: message.into(),
+    }
+}
+
+/// Parse all whitespace separated floats of a statement
+///
+fn parse_floats<'a>(
+    line: usize,
+    args: impl Iterator<Item = &'a str>,
+) -> Result<Vec<f32>, ObjError> {
+    args.map(|arg| {
+        arg.parse::<f32>()
+            .map_err(|_| parse_error(line, format!("invalid number `{}`", arg)))
+    })
+    .collect()
+}
+
+fn parse_vector<'a>(
+    line: usize,
+    args: impl Iterator<Item = &'a str>,
+) -> Result<Vector3D, ObjError> {
+    match parse_floats(line, args)?.as_slice() {
+        [x, y, z, ..] => Ok(Vector3D::new(*x, *y, *z)),
+        _ => Err(parse_error(line, "expected 3 coordinates")),
+    }
+}
+
+/// Material parameters of the MTL material library
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct MtlMaterial {
+    /// `Kd`
+    pub diffuse: ColorRGB,
+    /// `Ks`
+    pub specular: ColorRGB,
+    /// `Ns`, 0 to 1000
+    pub shininess: f32,
+    /// `d` or 1 - `Tr`, 1 is fully opaque
+    pub dissolve: f32,
+    /// `Ni`
+    pub refraction_index: f32,
+    /// `Ke`
+    pub emission: ColorRGB,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        Self {
+            diffuse: ColorRGB::new(0.5, 0.5, 0.5),
+            specular: ColorRGB::zero(),
+            shininess: 0.0,
+            dissolve: 1.0,
+            refraction_index: 1.5,
+            emission: ColorRGB::zero(),
+        }
+    }
+}
+
+impl MtlMaterial {
+    /// closest of the basic materials, emissive materials become lights,
+    /// transparent become dielectrics and mostly specular become metals
+    /// with the fuzz following the shininess
+    ///
+    pub fn to_material(&self) -> Material {
+        let luminance = |c: &ColorRGB| 0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z;
+        if luminance(&self.emission) > 0.0 {
+            Material::DiffuseLight(self.emission, 1.0)
+        } else if self.dissolve < 1.0 {
+            Material::Dielectric(self.refraction_index)
+        } else if luminance(&self.specular) > luminance(&self.diffuse) {
+            let fuzz = (2.0 / (self.shininess.max(0.0) + 2.0)).sqrt();
+            Material::Metal(self.specular.into(), fuzz)
+        } else {
+            Material::Lambertan(self.diffuse.into())
+        }
+    }
+}
+
+/// Parse MTL material library into materials by their names
+///
+pub fn parse_mtl(src: &str) -> Result<HashMap<String, MtlMaterial>, ObjError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+
+    for (n, text) in src.lines().enumerate() {
+        let line = n + 1;
+        let mut args = text.split_whitespace();
+        let keyword = match args.next() {
+            Some(keyword) if !keyword.starts_with('#') => keyword,
+            _ => continue,
+        };
+
+        if keyword == "newmtl" {
+            let name = args.collect::<Vec<_>>().join(" ");
+            if name.is_empty() {
+                return Err(parse_error(line, "material without a name"));
+            }
+            if let Some((name, material)) = current.take() {
+                materials.insert(name, material);
+            }
+            current = Some((name, MtlMaterial::default()));
+            continue;
+        }
+
+        let material = match current.as_mut() {
+            Some((_, material)) => material,
+            None => return Err(parse_error(line, format!("`{}` before `newmtl`", keyword))),
+        };
+        match keyword {
+            "Kd" => material.diffuse = parse_vector(line, args)?,
+            "Ks" => material.specular = parse_vector(line, args)?,
+            "Ke" => material.emission = parse_vector(line, args)?,
+            "Ns" | "Ni" | "d" | "Tr" => {
+                let value = match parse_floats(line, args)?.as_slice() {
+                    [value, ..] => *value,
+                    _ => return Err(parse_error(line, format!("`{}` without a value", keyword))),
+                };
+                match keyword {
+                    "Ns" => material.shininess = value,
+                    "Ni" => material.refraction_index = value,
+                    "d" => material.dissolve = value,
+                    _ => material.dissolve = 1.0 - value,
+                }
+            }
+            // textures, illumination models and other statements are not supported
+            _ => {}
+        }
+    }
+
+    if let Some((name, material)) = current {
+        materials.insert(name, material);
+    }
+    Ok(materials)
+}
+
+/// Placement of the model in the scene applied to its vertices while loading,
+/// the model is scaled, then rotated around Y axis and then translated
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Placement {
+    pub scale: f32,
+    /// rotation around Y axis in degrees
+    pub rotate_y: f32,
+    pub translate: Vector3D,
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            rotate_y: 0.0,
+            translate: Vector3D::zero(),
+        }
+    }
+}
+
+impl Placement {
+    /// error if the scale is zero or not finite
+    ///
+    pub fn transform(&self) -> Result<Transform, ObjError> {
+        if self.scale == 0.0 || !self.scale.is_finite() {
+            return Err(ObjError::InvalidScale(self.scale));
+        }
+        Ok(
+            Transform::scale(Vector3D::new(self.scale, self.scale, self.scale))
+                .then(&Transform::rotate_y(self.rotate_y))
+                .then(&Transform::translate(self.translate)),
+        )
+    }
+}
+
+/// Face corner with its indices into the OBJ position,
+/// texture coordinate and normal buffers
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct Corner {
+    position: usize,
+    texcoord: Option<usize>,
+    normal: Option<usize>,
+}
+
+/// Faces of a single material group
+///
+struct FaceGroup {
+    material: Option<String>,
+    faces: Vec<[Corner; 3]>,
+}
+
+/// Resolve 1 based or negative relative OBJ index into a 0 based one
+///
+fn resolve_index(line: usize, index: &str, count: usize) -> Result<usize, ObjError> {
+    let value: i64 = index
+        .parse()
+        .map_err(|_| parse_error(line, format!("invalid index `{}`", index)))?;
+    let resolved = if value > 0 {
+        value - 1
+    } else {
+        count as i64 + value
+    };
+    if value == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(parse_error(
+            line,
+            format!("index {} is out of {} elements", value, count),
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+/// Parse the `v`, `v/vt`, `v//vn` or `v/vt/vn` face corner
+///
+fn parse_corner(
+    line: usize,
+    corner: &str,
+    positions: usize,
+    texcoords: usize,
+    normals: usize,
+) -> Result<Corner, ObjError> {
+    let mut parts = corner.split('/');
+    let position = resolve_index(line, parts.next().unwrap_or(""), positions)?;
+    let mut optional_index = |count| match parts.next() {
+        Some(index) if !index.is_empty() => resolve_index(line, index, count).map(Some),
+        _ => Ok(None),
+    };
+    let texcoord = optional_index(texcoords)?;
+    let normal = optional_index(normals)?;
+    Ok(Corner {
+        position,
+        texcoord,
+        normal,
+    })
+}
+
+/// Parse the `vt` texture coordinates, v and w are optional
+///
+fn parse_texcoord<'a>(
+    line: usize,
+    args: impl Iterator<Item = &'a str>,
+) -> Result<TexCoord, ObjError> {
+    match parse_floats(line, args)?.as_slice() {
+        [u] => Ok((*u, 0.0)),
+        [u, v, ..] => Ok((*u, *v)),
+        _ => Err(parse_error(line, "expected texture coordinates")),
+    }
+}
+
+/// Parse OBJ model into triangle meshes, one mesh per material group,
+/// `mtllib` statements are ignored and the materials are looked up in `materials`
+///
+pub fn parse_obj(
+    src: &str,
+    materials: &HashMap<String, MtlMaterial>,
+    placement: &Placement,
+) -> Result<Vec<Mesh>, ObjError> {
+    let transform = placement.transform()?;
+    let mut positions = Vec::new();
+    let mut texcoords = Vec::new();
+    let mut normals = Vec::new();
+    let mut groups = vec![FaceGroup {
+        material: None,
+        faces: Vec::new(),
+    }];
+
+    for (n, text) in src.lines().enumerate() {
+        let line = n + 1;
+        let mut args = text.split_whitespace();
+        match args.next() {
+            Some("v") => positions.push(transform.point(&parse_vector(line, args)?)),
+            Some("vt") => texcoords.push(parse_texcoord(line, args)?),
+            Some("vn") => normals.push(transform.normal(&parse_vector(line, args)?).unit()),
+            Some("f") => {
+                let corners = args
+                    .map(|corner| {
+                        parse_corner(
+                            line,
+                            corner,
+                            positions.len(),
+                            texcoords.len(),
+                            normals.len(),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if corners.len() < 3 {
+                    return Err(parse_error(line, "face with less than 3 vertices"));
+                }
+                // triangulate polygons as a fan around the first vertex
+                let group = groups.last_mut().unwrap();
+                for k in 1..corners.len() - 1 {
+                    group.faces.push([corners[0], corners[k], corners[k + 1]]);
+                }
+            }
+            Some("usemtl") => {
+                let name = args.collect::<Vec<_>>().join(" ");
+                if !materials.contains_key(&name) {
+                    return Err(ObjError::UnknownMaterial(name));
+                }
+                groups.push(FaceGroup {
+                    material: Some(name),
+                    faces: Vec::new(),
+                });
+            }
+            // comments, objects, groups and smoothing groups are not supported
+            _ => {}
+        }
+    }
+
+    let meshes = groups
+        .into_iter()
+        .filter(|group| !group.faces.is_empty())
+        .map(|group| {
+            let material = group
+                .material
+                .map_or_else(MtlMaterial::default, |name| materials[&name].clone());
+            build_mesh(
+                &positions,
+                &texcoords,
+                &normals,
+                &group.faces,
+                material.to_material(),
+            )
+        })
+        .collect();
+    Ok(meshes)
+}
+
+/// Build a mesh out of the faces re-indexing the vertices so that every
+/// position, texture coordinate and normal triple becomes a single mesh vertex
+///
+fn build_mesh(
+    positions: &[Vector3D],
+    texcoords: &[TexCoord],
+    normals: &[Vector3D],
+    faces: &[[Corner; 3]],
+    material: Material,
+) -> Mesh {
+    // smooth shading only if every face corner has a normal
+    let is_smooth = faces.iter().flatten().all(|corner| corner.normal.is_some());
+    // faces without texture coordinates fall back to barycentrics on their own
+    let is_textured = faces
+        .iter()
+        .flatten()
+        .any(|corner| corner.texcoord.is_some());
+
+    let mut vertex_ids: HashMap<Corner, u32> = HashMap::new();
+    let mut mesh_positions = Vec::new();
+    let mut mesh_texcoords = Vec::new();
+    let mut mesh_normals = Vec::new();
+    let mut indices = Vec::with_capacity(faces.len());
+    for face in faces {
+        let triangle = face.map(|corner| {
+            let corner = Corner {
+                normal: corner.normal.filter(|_| is_smooth),
+                ..corner
+            };
+            *vertex_ids.entry(corner).or_insert_with(|| {
+                mesh_positions.push(positions[corner.position]);
+                if is_textured {
+                    mesh_texcoords.push(corner.texcoord.map(|i| texcoords[i]));
+                }
+                if let Some(normal) = corner.normal {
+                    mesh_normals.push(normals[normal]);
+                }
+                (mesh_positions.len() - 1) as u32
+            })
+        });
+        indices.push(triangle);
+    }
+    Mesh::with_texcoords(
+        mesh_positions,
+        mesh_normals,
+        mesh_texcoords,
+        indices,
+        material,
+    )
+}
+
+/// Load OBJ model together with its MTL material libraries
+/// located relative to the model file
+///
+pub fn load_obj<P: AsRef<Path>>(path: P, placement: &Placement) -> Result<Vec<Mesh>, ObjError> {
+    let src = std::fs::read_to_string(&path)?;
+    let base_dir = path.as_ref().parent().unwrap_or_else(|| Path::new(""));
+
+    let mut materials = HashMap::new();
+    for text in src.lines() {
+        let mut args = text.split_whitespace();
+        if args.next() == Some("mtllib") {
+            for library in args {
+                let mtl = std::fs::read_to_string(base_dir.join(library))?;
+                materials.extend(parse_mtl(&mtl)?);
+            }
+        }
+    }
+    parse_obj(&src, &materials, placement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUAD: &str = "
+        # unit quad at z = -2 split into two materials
+        mtllib quad.mtl
+        v -1 -1 -2
+        v 1 -1 -2
+        v 1 1 -2
+        v -1 1 -2
+        vn 0 0 1
+        usemtl red
+        f 1//1 2//1 3//1
+        usemtl mirror
+        f -4//-1 -2//-1 -1//-1
+    ";
+
+    const QUAD_MTL: &str = "
+        newmtl red
+        Kd 0.8 0.1 0.1
+        newmtl mirror
+        Kd 0.0 0.0 0.0
+        Ks 0.9 0.9 0.9
+        Ns 1000
+    ";
+
+    fn hit_material(meshes: &[Mesh], origin: Vector3D) -> Option<&Material> {
+        let ray = Ray::new(origin, -Vector3D::unit_z());
+        meshes
+            .iter()
+            .find_map(|mesh| mesh.hit(&ray, 0.0, f32::INFINITY))
+            .map(|record| record.material)
+    }
+
+    #[test]
+    fn test_parse_mtl() {
+        let materials = parse_mtl(QUAD_MTL).unwrap();
+        assert_eq!(materials.len(), 2);
+        assert_vec_eq(&materials["red"].diffuse, &ColorRGB::new(0.8, 0.1, 0.1));
+        assert_almost_eq(materials["mirror"].shininess, 1000.0);
+        assert_almost_eq(materials["mirror"].dissolve, 1.0);
+    }
+
+    #[test]
+    fn test_mtl_to_material() {
+        let materials = parse_mtl(
+            "
+            newmtl glass
+            d 0.1
+            Ni 1.33
+            newmtl lamp
+            Ke 4 4 4
+            ",
+        )
+        .unwrap();
+        assert!(matches!(materials["glass"].to_material(), Material::Dielectric(ri) if ri == 1.33));
+        assert!(matches!(
+            materials["lamp"].to_material(),
+            Material::DiffuseLight(..)
+        ));
+    }
+
+    #[test]
+    fn test_parse_obj_material_groups() {
+        let materials = parse_mtl(QUAD_MTL).unwrap();
+        let meshes = parse_obj(QUAD, &materials, &Placement::default()).unwrap();
+        assert_eq!(meshes.len(), 2);
+        assert!(meshes.iter().all(|mesh| mesh.face_count() == 1));
+
+        let lower_right = hit_material(&meshes, Vector3D::new(0.5, -0.5, 0.0));
+        assert!(matches!(lower_right, Some(Material::Lambertan(_))));
+        let upper_left = hit_material(&meshes, Vector3D::new(-0.5, 0.5, 0.0));
+        assert!(matches!(upper_left, Some(Material::Metal(_, fuzz)) if *fuzz < 0.1));
+    }
+
+    #[test]
+    fn test_parse_obj_polygon_and_placement() {
+        let obj = "
+            v 0 0 0
+            v 1 0 0
+            v 1 1 0
+            v 0 1 0
+            f 1 2 3 4
+        ";
+        let placement = Placement {
+            scale: 2.0,
+            rotate_y: 90.0,
+            translate: Vector3D::new(0.0, 0.0, -5.0),
+        };
+        let meshes = parse_obj(obj, &HashMap::new(), &placement).unwrap();
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0].face_count(), 2);
+
+        // quad in XY plane is rotated into ZY plane facing +X
+        let bbox = meshes[0].bounding_box().unwrap();
+        assert!((bbox.min.z + 7.0).abs() < 1e-3 && (bbox.max.z + 5.0).abs() < 1e-3);
+        assert!(bbox.max.x.abs() < 1e-3 && (bbox.max.y - 2.0).abs() < 1e-3);
+
+        let ray = Ray::new(Vector3D::new(5.0, 1.0, -6.0), -Vector3D::unit_x());
+        let record = meshes[0].hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert!((record.t - 5.0).abs() < 1e-4);
+        assert!((record.normal.x - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_parse_obj_texcoords() {
+        let obj = "
+            v -1 -1 -2
+            v 1 -1 -2
+            v 1 1 -2
+            v -1 1 -2
+            v -1 3 -2
+            vt 0 0
+            vt 1 0
+            vt 1 1
+            vt 0 1
+            f 1/1 2/2 3/3 4/4
+            f 4 3 5
+        ";
+        let meshes = parse_obj(obj, &HashMap::new(), &Placement::default()).unwrap();
+        let uv = |x, y| {
+            let ray = Ray::new(Vector3D::new(x, y, 0.0), -Vector3D::unit_z());
+            let record = meshes[0].hit(&ray, 0.0, f32::INFINITY).unwrap();
+            (record.u, record.v)
+        };
+        let (u, v) = uv(0.5, -0.5);
+        assert_almost_eq(u, 0.75);
+        assert_almost_eq(v, 0.25);
+        let (u, v) = uv(-0.5, 0.5);
+        assert_almost_eq(u, 0.25);
+        assert_almost_eq(v, 0.75);
+        // face without `vt` uses its barycentric coordinates
+        let (u, v) = uv(-0.5, 1.5);
+        assert_almost_eq(u, 0.25);
+        assert_almost_eq(v, 0.25);
+
+        let out_of_bounds = "v 0 0 0\nv 1 0 0\nv 1 1 0\nvt 0 0\nf 1/1 2/2 3/3";
+        assert!(matches!(
+            parse_obj(out_of_bounds, &HashMap::new(), &Placement::default()),
+            Err(ObjError::Parse { line: 5, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_obj_errors() {
+        let no_materials = HashMap::new();
+        let placement = Placement::default();
+
+        let out_of_bounds = "v 0 0 0\nv 1 0 0\nf 1 2 3";
+        assert!(matches!(
+            parse_obj(out_of_bounds, &no_materials, &placement),
+            Err(ObjError::Parse { line: 3, .. })
+        ));
+
+        let bad_number = "v 0 zero 0";
+        assert!(matches!(
+            parse_obj(bad_number, &no_materials, &placement),
+            Err(ObjError::Parse { line: 1, .. })
+        ));
+
+        let unknown_material = "usemtl gold";
+        assert!(matches!(
+            parse_obj(unknown_material, &no_materials, &placement),
+            Err(ObjError::UnknownMaterial(name)) if name == "gold"
+        ));
+
+        for scale in [0.0, f32::NAN, f32::INFINITY] {
+            let placement = Placement {
+                scale,
+                ..Default::default()
+            };
+            assert!(matches!(
+                parse_obj(QUAD, &no_materials, &placement),
+                Err(ObjError::InvalidScale(_))
+            ));
+        }
+    }
+}
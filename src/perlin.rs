@@ -0,0 +1,118 @@
+use crate::prelude::*;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+/// Gradient noise with random unit vectors on the integer lattice
+/// https://raytracing.github.io/books/RayTracingTheNextWeek.html#perlinnoise
+///
+pub struct Perlin {
+    gradients: Vec<Vector3D>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    const POINT_COUNT: usize = 256;
+
+    /// noise with permutation tables generated from the seed,
+    /// the same seed always produces the same noise
+    ///
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+        let gradients = (0..Self::POINT_COUNT)
+            .map(|_| Vector3D::unit_sphere_sample(&mut rng))
+            .collect();
+        let mut permutation = || {
+            let mut perm: Vec<usize> = (0..Self::POINT_COUNT).collect();
+            perm.shuffle(&mut rng);
+            perm
+        };
+        let (perm_x, perm_y, perm_z) = (permutation(), permutation(), permutation());
+        Self {
+            gradients,
+            perm_x,
+            perm_y,
+            perm_z,
+        }
+    }
+
+    /// smooth noise in range -1 to 1, zero at the lattice points
+    ///
+    pub fn noise(&self, p: &Vector3D) -> f32 {
+        let floor = Vector3D::new(p.x.floor(), p.y.floor(), p.z.floor());
+        let frac = p - floor;
+        // Hermite smoothing of the interpolation weights
+        let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+        let (u, v, w) = (smooth(frac.x), smooth(frac.y), smooth(frac.z));
+
+        let mask = Self::POINT_COUNT as i64 - 1;
+        let (i, j, k) = (floor.x as i64, floor.y as i64, floor.z as i64);
+        let mut accum = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let index = self.perm_x[((i + di) & mask) as usize]
+                        ^ self.perm_y[((j + dj) & mask) as usize]
+                        ^ self.perm_z[((k + dk) & mask) as usize];
+                    let (fi, fj, fk) = (di as f32, dj as f32, dk as f32);
+                    let weight = Vector3D::new(frac.x - fi, frac.y - fj, frac.z - fk);
+                    accum += (fi * u + (1.0 - fi) * (1.0 - u))
+                        * (fj * v + (1.0 - fj) * (1.0 - v))
+                        * (fk * w + (1.0 - fk) * (1.0 - w))
+                        * self.gradients[index].dot(&weight);
+                }
+            }
+        }
+        accum
+    }
+
+    /// sum of `depth` octaves of noise with halving amplitude
+    /// and doubling frequency, always positive
+    ///
+    pub fn turbulence(&self, p: &Vector3D, depth: u32) -> f32 {
+        let mut accum = 0.0;
+        let mut p = *p;
+        let mut weight = 1.0;
+        for _ in 0..depth {
+            accum += weight * self.noise(&p);
+            weight *= 0.5;
+            p *= 2.0;
+        }
+        accum.abs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_range_and_lattice() {
+        let perlin = Perlin::new(7);
+        assert_almost_eq(perlin.noise(&Vector3D::new(3.0, -2.0, 5.0)), 0.0);
+        for i in 0..1000 {
+            let t = i as f32 * 0.137;
+            let p = Vector3D::new(t, -0.7 * t, 0.3 * t + 0.5);
+            let n = perlin.noise(&p);
+            assert!((-1.0..=1.0).contains(&n));
+            assert!(perlin.turbulence(&p, 7) >= 0.0);
+        }
+    }
+
+    #[test]
+    fn test_noise_is_seeded() {
+        let p = Vector3D::new(1.3, 2.7, -0.4);
+        assert_eq!(Perlin::new(1).noise(&p), Perlin::new(1).noise(&p));
+        assert_ne!(Perlin::new(1).noise(&p), Perlin::new(2).noise(&p));
+    }
+
+    #[test]
+    fn test_noise_is_continuous() {
+        let perlin = Perlin::new(0);
+        let p = Vector3D::new(0.999, 0.5, 0.5);
+        let q = Vector3D::new(1.001, 0.5, 0.5);
+        assert!((perlin.noise(&p) - perlin.noise(&q)).abs() < 0.01);
+    }
+}
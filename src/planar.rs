@@ -0,0 +1,416 @@
+use crate::prelude::*;
+use rand::{Rng, RngCore};
+
+/// Infinite plane through a point, its front face
+/// is on the side the normal points to
+///
+pub struct Plane {
+    pub point: Vector3D,
+    pub normal: Vector3D,
+    pub material: Material,
+}
+
+impl Plane {
+    /// plane through `point`, the normal does not need to be unit
+    ///
+    pub fn new(point: Vector3D, normal: Vector3D, material: Material) -> Self {
+        Self {
+            point,
+            normal: normal.unit(),
+            material,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let denom = self.normal.dot(&ray.direction);
+        // ray is parallel to the plane
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+        let t = self.normal.dot(&(self.point - ray.origin)) / denom;
+        if (t < t_min) | (t > t_max) {
+            return None;
+        }
+        let point = ray.at(t);
+        // world space distances along the plane tangents
+        let (tangent, bitangent) = tangent_frame(&self.normal);
+        let offset = point - self.point;
+        let (u, v) = (offset.dot(&tangent), offset.dot(&bitangent));
+        let mut record = HitRecord::new(point, t, self.normal, &self.material).with_uv(u, v);
+        record.set_ray_facing_normal(ray);
+        Some(record)
+    }
+
+    /// plane is unbounded
+    ///
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+}
+
+/// Rectangle perpendicular to the `axis` placed at `k`, spanning `a` and `b`
+/// ranges along the other two axes taken in x, y, z order
+///
+struct AxisRect {
+    axis: usize,
+    a: [f32; 2],
+    b: [f32; 2],
+    k: f32,
+    /// +1 if the front face looks along the axis, -1 if against it
+    facing: f32,
+}
+
+impl AxisRect {
+    fn new(axis: usize, a: [f32; 2], b: [f32; 2], k: f32, facing: f32) -> Self {
+        Self {
+            axis,
+            a: [a[0].min(a[1]), a[0].max(a[1])],
+            b: [b[0].min(b[1]), b[0].max(b[1])],
+            k,
+            facing,
+        }
+    }
+
+    fn other_axes(&self) -> (usize, usize) {
+        match self.axis {
+            0 => (1, 2),
+            1 => (0, 2),
+            _ => (0, 1),
+        }
+    }
+
+    /// ray parameter t of the intersection with the rectangle
+    ///
+    fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<f32> {
+        let t = (self.k - ray.origin[self.axis]) / ray.direction[self.axis];
+        // also rejects NaN of rays parallel to the rectangle
+        if !(t >= t_min && t <= t_max) {
+            return None;
+        }
+        let point = ray.at(t);
+        let (a_axis, b_axis) = self.other_axes();
+        let (a, b) = (point[a_axis], point[b_axis]);
+        if a < self.a[0] || a > self.a[1] || b < self.b[0] || b > self.b[1] {
+            return None;
+        }
+        Some(t)
+    }
+
+    fn hit<'a>(
+        &self,
+        ray: &Ray,
+        t_min: f32,
+        t_max: f32,
+        material: &'a Material,
+    ) -> Option<HitRecord<'a>> {
+        let t = self.intersect(ray, t_min, t_max)?;
+        let point = ray.at(t);
+        let (a_axis, b_axis) = self.other_axes();
+        let (a, b) = (point[a_axis], point[b_axis]);
+
+        let u = (a - self.a[0]) / (self.a[1] - self.a[0]);
+        let v = (b - self.b[0]) / (self.b[1] - self.b[0]);
+        let normal = [Vector3D::unit_x(), Vector3D::unit_y(), Vector3D::unit_z()][self.axis];
+        let mut record = HitRecord::new(point, t, normal * self.facing, material).with_uv(u, v);
+        record.set_ray_facing_normal(ray);
+        Some(record)
+    }
+
+    fn area(&self) -> f32 {
+        (self.a[1] - self.a[0]) * (self.b[1] - self.b[0])
+    }
+
+    /// unit direction from the origin towards a uniformly sampled point
+    ///
+    fn sample_direction(&self, origin: &Vector3D, rng: &mut dyn RngCore) -> Vector3D {
+        let (a_axis, b_axis) = self.other_axes();
+        let mut point = [0.0; 3];
+        point[self.axis] = self.k;
+        point[a_axis] = rng.gen_range(self.a[0]..=self.a[1]);
+        point[b_axis] = rng.gen_range(self.b[0]..=self.b[1]);
+        (Vector3D::from(point) - *origin).unit()
+    }
+
+    /// uniform area density converted to the solid angle seen from the origin
+    ///
+    fn pdf(&self, origin: &Vector3D, direction: &Vector3D) -> f32 {
+        let ray = Ray::new(*origin, *direction);
+        match self.intersect(&ray, 1e-4, f32::INFINITY) {
+            // unit direction so t is the distance
+            Some(t) => t * t / (ray.direction[self.axis].abs() * self.area()),
+            None => 0.0,
+        }
+    }
+
+    /// box padded along the axis so that it is not infinitely thin
+    ///
+    fn bounding_box(&self) -> Aabb {
+        let (a_axis, b_axis) = self.other_axes();
+        let mut min = [0.0; 3];
+        let mut max = [0.0; 3];
+        min[self.axis] = self.k - 1e-4;
+        max[self.axis] = self.k + 1e-4;
+        min[a_axis] = self.a[0];
+        max[a_axis] = self.a[1];
+        min[b_axis] = self.b[0];
+        max[b_axis] = self.b[1];
+        Aabb::new(min.into(), max.into())
+    }
+}
+
+/// Rectangle in the plane z = k with the front face towards +Z
+///
+pub struct XyRect {
+    rect: AxisRect,
+    pub material: Material,
+}
+
+impl XyRect {
+    pub fn new(x: [f32; 2], y: [f32; 2], k: f32, material: Material) -> Self {
+        Self {
+            rect: AxisRect::new(2, x, y, k, 1.0),
+            material,
+        }
+    }
+}
+
+impl Hittable for XyRect {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.rect.hit(ray, t_min, t_max, &self.material)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.rect.bounding_box())
+    }
+}
+
+impl Light for XyRect {
+    fn sample_direction(&self, origin: &Vector3D, rng: &mut dyn RngCore) -> Vector3D {
+        self.rect.sample_direction(origin, rng)
+    }
+
+    fn pdf(&self, origin: &Vector3D, direction: &Vector3D) -> f32 {
+        self.rect.pdf(origin, direction)
+    }
+}
+
+/// Rectangle in the plane y = k with the front face towards +Y
+///
+pub struct XzRect {
+    rect: AxisRect,
+    pub material: Material,
+}
+
+impl XzRect {
+    pub fn new(x: [f32; 2], z: [f32; 2], k: f32, material: Material) -> Self {
+        Self {
+            rect: AxisRect::new(1, x, z, k, 1.0),
+            material,
+        }
+    }
+}
+
+impl Hittable for XzRect {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.rect.hit(ray, t_min, t_max, &self.material)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.rect.bounding_box())
+    }
+}
+
+impl Light for XzRect {
+    fn sample_direction(&self, origin: &Vector3D, rng: &mut dyn RngCore) -> Vector3D {
+        self.rect.sample_direction(origin, rng)
+    }
+
+    fn pdf(&self, origin: &Vector3D, direction: &Vector3D) -> f32 {
+        self.rect.pdf(origin, direction)
+    }
+}
+
+/// Rectangle in the plane x = k with the front face towards +X
+///
+pub struct YzRect {
+    rect: AxisRect,
+    pub material: Material,
+}
+
+impl YzRect {
+    pub fn new(y: [f32; 2], z: [f32; 2], k: f32, material: Material) -> Self {
+        Self {
+            rect: AxisRect::new(0, y, z, k, 1.0),
+            material,
+        }
+    }
+}
+
+impl Hittable for YzRect {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.rect.hit(ray, t_min, t_max, &self.material)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.rect.bounding_box())
+    }
+}
+
+impl Light for YzRect {
+    fn sample_direction(&self, origin: &Vector3D, rng: &mut dyn RngCore) -> Vector3D {
+        self.rect.sample_direction(origin, rng)
+    }
+
+    fn pdf(&self, origin: &Vector3D, direction: &Vector3D) -> f32 {
+        self.rect.pdf(origin, direction)
+    }
+}
+
+/// Axis aligned box made of six rectangles with front faces outside
+///
+pub struct Cuboid {
+    pub min: Vector3D,
+    pub max: Vector3D,
+    sides: [AxisRect; 6],
+    pub material: Material,
+}
+
+impl Cuboid {
+    /// box spanned between two opposite corners
+    ///
+    pub fn new(p0: Vector3D, p1: Vector3D, material: Material) -> Self {
+        let min = Vector3D::new(p0.x.min(p1.x), p0.y.min(p1.y), p0.z.min(p1.z));
+        let max = Vector3D::new(p0.x.max(p1.x), p0.y.max(p1.y), p0.z.max(p1.z));
+        let (x, y, z) = ([min.x, max.x], [min.y, max.y], [min.z, max.z]);
+        Self {
+            min,
+            max,
+            sides: [
+                AxisRect::new(0, y, z, max.x, 1.0),
+                AxisRect::new(0, y, z, min.x, -1.0),
+                AxisRect::new(1, x, z, max.y, 1.0),
+                AxisRect::new(1, x, z, min.y, -1.0),
+                AxisRect::new(2, x, y, max.z, 1.0),
+                AxisRect::new(2, x, y, min.z, -1.0),
+            ],
+            material,
+        }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let mut record = None;
+        let mut t_closest = t_max;
+        for side in self.sides.iter() {
+            if let Some(temp_record) = side.hit(ray, t_min, t_closest, &self.material) {
+                t_closest = temp_record.t;
+                record = Some(temp_record);
+            }
+        }
+        record
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::new(self.min, self.max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hit_plane() {
+        let plane = Plane::new(
+            Vector3D::new(0.0, -1.0, 0.0),
+            Vector3D::unit_y() * 2.0,
+            Material::None,
+        );
+        let ray = Ray::new(Vector3D::zero(), Vector3D::new(0.0, -1.0, -1.0));
+        let record = plane.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert_vec_eq(&record.point, &Vector3D::new(0.0, -1.0, -1.0));
+        assert_vec_eq(&record.normal, &Vector3D::unit_y());
+        assert!(record.is_front_face);
+        assert!(plane.bounding_box().is_none());
+
+        let below = Ray::new(Vector3D::new(0.0, -2.0, 0.0), Vector3D::unit_y());
+        let record = plane.hit(&below, 0.0, f32::INFINITY).unwrap();
+        assert_almost_eq(record.t, 1.0);
+        assert!(!record.is_front_face);
+        assert_vec_eq(&record.normal, &-Vector3D::unit_y());
+
+        let parallel = Ray::new(Vector3D::zero(), Vector3D::unit_x());
+        assert!(plane.hit(&parallel, 0.0, f32::INFINITY).is_none());
+        let away = Ray::new(Vector3D::zero(), Vector3D::unit_y());
+        assert!(plane.hit(&away, 0.0, f32::INFINITY).is_none());
+    }
+
+    #[test]
+    fn test_hit_rects() {
+        let xy = XyRect::new([-1.0, 1.0], [-1.0, 1.0], -2.0, Material::None);
+        let ray = Ray::new(Vector3D::new(0.5, 0.5, 0.0), -Vector3D::unit_z());
+        let record = xy.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert_almost_eq(record.t, 2.0);
+        assert_almost_eq(record.u, 0.75);
+        assert_almost_eq(record.v, 0.75);
+        assert_vec_eq(&record.normal, &Vector3D::unit_z());
+        assert!(record.is_front_face);
+        let outside = Ray::new(Vector3D::new(1.5, 0.0, 0.0), -Vector3D::unit_z());
+        assert!(xy.hit(&outside, 0.0, f32::INFINITY).is_none());
+
+        let xz = XzRect::new([1.0, -1.0], [-1.0, 1.0], 1.0, Material::None);
+        let ray = Ray::new(Vector3D::zero(), Vector3D::unit_y());
+        let record = xz.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert!(!record.is_front_face);
+        assert_vec_eq(&record.normal, &-Vector3D::unit_y());
+
+        let yz = YzRect::new([0.0, 1.0], [0.0, 1.0], 3.0, Material::None);
+        let ray = Ray::new(Vector3D::new(5.0, 0.5, 0.5), -Vector3D::unit_x());
+        let record = yz.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert_vec_eq(&record.point, &Vector3D::new(3.0, 0.5, 0.5));
+        assert_almost_eq(record.u, 0.5);
+        assert_almost_eq(record.v, 0.5);
+        assert!(record.is_front_face);
+
+        let bbox = yz.bounding_box().unwrap();
+        assert!(bbox.min.x < 3.0 && bbox.max.x > 3.0);
+        assert_vec_eq(&bbox.max, &Vector3D::new(bbox.max.x, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_hit_cuboid() {
+        let cuboid = Cuboid::new(
+            Vector3D::new(1.0, 1.0, -3.0),
+            Vector3D::new(-1.0, -1.0, -5.0),
+            Material::None,
+        );
+        assert_vec_eq(&cuboid.min, &Vector3D::new(-1.0, -1.0, -5.0));
+
+        // every face is hit from outside as a front face with outward normal
+        let directions = [
+            Vector3D::unit_x(),
+            Vector3D::unit_y(),
+            Vector3D::unit_z(),
+            -Vector3D::unit_x(),
+            -Vector3D::unit_y(),
+            -Vector3D::unit_z(),
+        ];
+        let center = Vector3D::new(0.0, 0.0, -4.0);
+        for direction in directions {
+            let ray = Ray::new(center + direction * 5.0, -direction);
+            let record = cuboid.hit(&ray, 0.0, f32::INFINITY).unwrap();
+            assert_almost_eq(record.t, 4.0);
+            assert!(record.is_front_face);
+            assert_vec_eq(&record.normal, &direction);
+
+            let inside = Ray::new(center, direction);
+            let record = cuboid.hit(&inside, 0.0, f32::INFINITY).unwrap();
+            assert_almost_eq(record.t, 1.0);
+            assert!(!record.is_front_face);
+            assert_vec_eq(&record.normal, &-direction);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use crate::prelude::*;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Running sum of the radiance samples of every pixel, progressive
+/// rendering adds one sample pass at a time and reads back the average
+///
+pub struct Accumulator {
+    width: u32,
+    height: u32,
+    passes: u32,
+    sum: Vec<ColorRGB>,
+}
+
+impl Accumulator {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            passes: 0,
+            sum: vec![ColorRGB::zero(); (width * height) as usize],
+        }
+    }
+
+    /// number of passes added so far
+    ///
+    pub fn passes(&self) -> u32 {
+        self.passes
+    }
+
+    /// add one sample for every pixel, `rows` are indexed like the image rows
+    ///
+    pub fn add_pass(&mut self, rows: Vec<Vec<ColorRGB>>) {
+        assert_eq!(rows.len(), self.height as usize, "pass row count mismatch");
+        for (j, row) in rows.into_iter().enumerate() {
+            assert_eq!(row.len(), self.width as usize, "pass row length mismatch");
+            let start = j * self.width as usize;
+            for (sum, color) in self.sum[start..].iter_mut().zip(row) {
+                *sum += color;
+            }
+        }
+        self.passes += 1;
+    }
+
+    /// average radiance of the pixel, zero before the first pass
+    ///
+    pub fn average(&self, i: u32, j: u32) -> ColorRGB {
+        if self.passes == 0 {
+            return ColorRGB::zero();
+        }
+        self.sum[(j * self.width + i) as usize] * (1.0 / self.passes as f32)
+    }
+
+    /// store the running average into the image of the same size
+    ///
+    pub fn write_average(&self, image: &mut Image) {
+        assert_eq!(
+            image.dims(),
+            (self.width, self.height),
+            "image size mismatch"
+        );
+        for j in 0..self.height {
+            for i in 0..self.width {
+                image.set_at(i, j, self.average(i, j));
+            }
+        }
+    }
+}
+
+/// How often the progressive preview is written
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PreviewInterval {
+    /// after every given number of passes
+    Passes(u32),
+    /// after the first pass finished at least this many seconds after the last write
+    Seconds(f32),
+}
+
+/// Writes snapshots of a progressive render to disk
+///
+pub struct PreviewWriter {
+    path: PathBuf,
+    format: ExportFormat,
+    interval: PreviewInterval,
+    last_write: Instant,
+}
+
+impl PreviewWriter {
+    pub fn new(path: impl AsRef<Path>, format: ExportFormat, interval: PreviewInterval) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            format,
+            interval,
+            last_write: Instant::now(),
+        }
+    }
+
+    /// write the image if the interval elapsed after `passes` finished
+    /// passes, returns whether the preview was written
+    ///
+    pub fn update(&mut self, image: &Image, passes: u32) -> Result<bool, ExportError> {
+        let due = match self.interval {
+            PreviewInterval::Passes(n) => passes.is_multiple_of(n.max(1)),
+            PreviewInterval::Seconds(s) => {
+                self.last_write.elapsed() >= Duration::from_secs_f32(s.max(0.0))
+            }
+        };
+        if due {
+            self.write(image)?;
+        }
+        Ok(due)
+    }
+
+    /// write the image right away
+    ///
+    pub fn write(&mut self, image: &Image) -> Result<(), ExportError> {
+        image.save_with_format(&self.path, self.format)?;
+        self.last_write = Instant::now();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_running_average() {
+        let mut accumulator = Accumulator::new(2, 1);
+        let mut image = Image::new(2, 1);
+        accumulator.write_average(&mut image);
+        assert_vec_eq(&image.at(0, 0), &ColorRGB::zero());
+
+        accumulator.add_pass(vec![vec![ColorRGB::new(1.0, 0.0, 0.0), ColorRGB::zero()]]);
+        accumulator.add_pass(vec![vec![ColorRGB::new(0.0, 0.0, 1.0), ColorRGB::zero()]]);
+        accumulator.add_pass(vec![vec![ColorRGB::new(2.0, 0.0, 0.0), ColorRGB::zero()]]);
+        accumulator.write_average(&mut image);
+        assert_eq!(accumulator.passes(), 3);
+        assert_vec_eq(&image.at(0, 0), &ColorRGB::new(1.0, 0.0, 1.0 / 3.0));
+        assert_vec_eq(&image.at(1, 0), &ColorRGB::zero());
+    }
+
+    #[test]
+    fn test_preview_every_passes() {
+        let path = std::env::temp_dir().join(format!("yarrr_preview_{}.ppm", std::process::id()));
+        let mut preview = PreviewWriter::new(&path, ExportFormat::Ppm, PreviewInterval::Passes(2));
+        let image = Image::new(2, 2);
+        let written: Vec<bool> = (1..=4)
+            .map(|n| preview.update(&image, n).unwrap())
+            .collect();
+        assert_eq!(written, vec![false, true, false, true]);
+        assert!(path.exists());
+        std::fs::remove_file(&path).unwrap();
+
+        let mut preview =
+            PreviewWriter::new(&path, ExportFormat::Ppm, PreviewInterval::Seconds(3600.0));
+        assert!(!preview.update(&image, 1).unwrap());
+        assert!(!path.exists());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::prelude::*;
+use std::sync::Arc;
 
 /// Ray primitive with origin and direction
 ///
@@ -6,6 +7,8 @@ use crate::prelude::*;
 pub struct Ray {
     pub origin: Vector3D,
     pub direction: Vector3D,
+    /// moment within the camera shutter interval the ray was shot at
+    pub time: f32,
 }
 
 impl Ray {
@@ -13,9 +16,17 @@ impl Ray {
         Self {
             origin,
             direction: direction.unit(),
+            time: 0.0,
         }
     }
 
+    /// same ray shot at a different time
+    ///
+    pub fn with_time(mut self, time: f32) -> Self {
+        self.time = time;
+        self
+    }
+
     /// Ray primitive with origin and direction
     ///
     #[inline]
@@ -32,6 +43,9 @@ pub struct HitRecord<'a> {
     pub normal: Vector3D,
     pub is_front_face: bool,
     pub material: &'a Material,
+    /// surface texture coordinates of the hit point
+    pub u: f32,
+    pub v: f32,
 }
 
 impl<'a> HitRecord<'a> {
@@ -42,9 +56,19 @@ impl<'a> HitRecord<'a> {
             normal,
             material,
             is_front_face: true,
+            u: 0.0,
+            v: 0.0,
         }
     }
 
+    /// same record with surface texture coordinates
+    ///
+    pub fn with_uv(mut self, u: f32, v: f32) -> Self {
+        self.u = u;
+        self.v = v;
+        self
+    }
+
     #[inline]
     /// Figure out wether we hit the front facing side of a body
     /// (normal towards ray) and flip the normal in case of back side
@@ -58,10 +82,35 @@ impl<'a> HitRecord<'a> {
 }
 
 /// trait for all bodies that can be potentially hit by ray and produce
-/// the hit record
+/// the hit record, bodies are shared between render threads
 ///
-pub trait Hittable {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord>;
+pub trait Hittable: Send + Sync {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>>;
+
+    /// box enclosing the body or None if the body is unbounded
+    ///
+    fn bounding_box(&self) -> Option<Aabb>;
+
+    /// emitters sampled directly as light sources by the renderer,
+    /// None if they can only be found by random bounces
+    ///
+    fn lights(&self) -> Option<&LightList> {
+        None
+    }
+}
+
+impl<T: Hittable + ?Sized> Hittable for Arc<T> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.as_ref().hit(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.as_ref().bounding_box()
+    }
+
+    fn lights(&self) -> Option<&LightList> {
+        self.as_ref().lights()
+    }
 }
 
 #[cfg(test)]
@@ -1,44 +1,169 @@
 use crate::prelude::*;
 use indicatif::{ProgressBar, ProgressStyle};
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicU64, Ordering};
 
-/// Converts RGB in range 0.0-1.0 to string with 0-255
+/// Writes the image to the standard output in the plain text PPM format
+/// https://raytracing.github.io/books/RayTracingInOneWeekend.html#outputanimage/theppmimageformat
 ///
-fn to_string(vec: &ColorRGB) -> String {
-    let r = (255.999 * vec.x) as u32;
-    let g = (255.999 * vec.y) as u32;
-    let b = (255.999 * vec.z) as u32;
-    format!("{} {} {} ", r, g, b)
+pub fn print_ppm(image: &Image) -> Result<(), ExportError> {
+    image.write_to(std::io::stdout().lock(), ExportFormat::PpmAscii)
 }
 
-/// Generates a string corresponding to PPM image format
-/// https://raytracing.github.io/books/RayTracingInOneWeekend.html#outputanimage/theppmimageformat
+/// How the render progress is reported
 ///
-pub fn print_ppm(image: &Image) {
-    println!("P3\n{} {}\n255", image.width, image.height);
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// interactive progress bar
+    Bar,
+    /// plain text lines suitable for non-interactive logs
+    Log,
+    /// no progress output
+    Hidden,
+}
 
-    // print row by row
-    for j in (0..image.height).rev() {
-        for i in 0..image.width {
-            print!("{}", to_string(&image.at(i, j)));
-        }
-        println!();
-    }
+/// How the renderer finds the light sources of the world, see `Hittable::lights`
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LightSampling {
+    /// lights are found only by random bounces, pure path tracing
+    None,
+    /// bounces go towards the lights or follow the material with equal probability
+    Mixture,
+    /// shadow ray towards the lights at every non-specular hit,
+    /// weighted against the bounces that hit the lights on their own
+    NextEvent,
 }
 
 /// Container for the renderer settings
 ///
 pub struct RenderSettings {
+    /// samples of every pixel, also the number of progressive passes
     pub samples_per_px: u32,
+    /// stop sampling pixels once their estimate is precise enough
+    /// instead of taking `samples_per_px` samples everywhere,
+    /// progressive rendering always takes `samples_per_px`
+    pub adaptive: Option<AdaptiveSampling>,
     pub bounce_depth: u32,
+    /// number of render threads, 0 uses all available cores
+    pub threads: usize,
+    pub progress: ProgressMode,
+    /// seed of the random sampling, same seed renders identical images
+    /// regardless of the thread count, None picks a random seed
+    pub seed: Option<u64>,
+    /// light coming from the rays that escape the scene
+    pub environment: Environment,
+    /// how the lights of the rendered world are sampled
+    pub light_sampling: LightSampling,
+    /// display transform stored in the rendered image for its 8 bit exports
+    pub tone_mapping: ToneMapping,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            samples_per_px: 100,
+            adaptive: None,
+            bounce_depth: 5,
+            threads: 0,
+            progress: ProgressMode::Bar,
+            seed: None,
+            environment: Environment::default(),
+            light_sampling: LightSampling::NextEvent,
+            tone_mapping: ToneMapping::default(),
+        }
+    }
+}
+
+/// Progress reporter that can be shared between render threads
+///
+struct RenderProgress {
+    bar: ProgressBar,
+    mode: ProgressMode,
+    done: AtomicU64,
+    total: u64,
+    /// name of the counted render steps in the log lines
+    unit: &'static str,
+}
+
+impl RenderProgress {
+    /// number of log lines written in the `ProgressMode::Log` mode
+    const LOG_LINES: u64 = 10;
+
+    fn new(total: u64, mode: ProgressMode, unit: &'static str) -> Self {
+        let bar = match mode {
+            ProgressMode::Bar => ProgressBar::new(total).with_style(
+                ProgressStyle::with_template(
+                    "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}",
+                )
+                .unwrap(),
+            ),
+            ProgressMode::Log | ProgressMode::Hidden => ProgressBar::hidden(),
+        };
+        Self {
+            bar,
+            mode,
+            done: AtomicU64::new(0),
+            total,
+            unit,
+        }
+    }
+
+    fn inc(&self) {
+        self.bar.inc(1);
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        let step = (self.total / Self::LOG_LINES).max(1);
+        if self.mode == ProgressMode::Log && (done.is_multiple_of(step) || done == self.total) {
+            eprintln!(
+                "[{:.1}s] rendered {}/{} {}",
+                self.bar.elapsed().as_secs_f32(),
+                done,
+                self.total,
+                self.unit
+            );
+        }
+    }
+
+    fn finish(&self) {
+        self.bar.finish();
+    }
 }
 
 /// Recursively sample bounces off the world objects and
 /// accumulate them as the color for the pixel that the ray
 /// is shot through
 ///
-pub fn collect_color<T>(ray: &Ray, world: &T, depth: u32) -> ColorRGB
+pub fn collect_color<T, R>(
+    ray: &Ray,
+    world: &T,
+    settings: &RenderSettings,
+    depth: u32,
+    rng: &mut R,
+) -> ColorRGB
 where
     T: Hittable + 'static,
+    R: Rng + ?Sized,
+{
+    trace(ray, world, settings, depth, None, rng)
+}
+
+/// `collect_color` of a ray that was scattered with the material density
+/// `bounce_pdf` when it comes from a surface that also sampled the lights
+/// with shadow rays, its emission is then weighted against the shadow rays
+///
+fn trace<T, R>(
+    ray: &Ray,
+    world: &T,
+    settings: &RenderSettings,
+    depth: u32,
+    bounce_pdf: Option<f32>,
+    rng: &mut R,
+) -> ColorRGB
+where
+    T: Hittable + 'static,
+    R: Rng + ?Sized,
 {
     if depth == 0 {
         return ColorRGB::new(0.0, 0.0, 0.0);
@@ -47,61 +172,553 @@ where
     // t_min value not too small to avoid shadow-acne problem
     // https://raytracing.github.io/books/RayTracingInOneWeekend.html#diffusematerials/fixingshadowacne
     //
-    if let Some(hitdata) = world.hit(ray, 1E-3, f32::INFINITY) {
-        if let Some(bounce) = Material::scatter(ray, &hitdata) {
-            bounce.attenuation * collect_color(&bounce.ray, world, depth - 1)
-        } else {
-            ColorRGB::new(0.0, 0.0, 0.0)
+    let Some(hitdata) = world.hit(ray, 1E-3, f32::INFINITY) else {
+        return settings.environment.color(ray);
+    };
+    let no_lights = LightList::new();
+    let lights = world.lights().unwrap_or(&no_lights);
+    let sampling = match lights.is_empty() {
+        true => LightSampling::None,
+        false => settings.light_sampling,
+    };
+
+    let mut color = Material::emitted(&hitdata);
+    if let Some(pdf) = bounce_pdf {
+        color *= power_heuristic(pdf, lights.pdf(&ray.origin, &ray.direction));
+    }
+    let Some(bounce) = Material::scatter(ray, &hitdata, rng) else {
+        return color;
+    };
+    let mut next_pdf = None;
+    let bounce = match (sampling, bounce.pdf) {
+        (LightSampling::Mixture, Some(_)) => {
+            match mix_light_sampling(ray, &hitdata, bounce, lights, rng) {
+                Some(bounce) => bounce,
+                None => return color,
+            }
         }
+        // lights seen by the shadow ray would be hit only after the last bounce
+        (LightSampling::NextEvent, Some(pdf)) if depth > 1 => {
+            color += sample_light(ray, &hitdata, world, lights, rng);
+            next_pdf = Some(pdf);
+            bounce
+        }
+        _ => bounce,
+    };
+    color + bounce.attenuation * trace(&bounce.ray, world, settings, depth - 1, next_pdf, rng)
+}
+
+/// Power heuristic weight of a sample taken with density `pdf`
+/// when the other technique would sample it with `other_pdf`
+/// https://pbr-book.org/3ed-2018/Monte_Carlo_Integration/Importance_Sampling#MultipleImportanceSampling
+///
+fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b > 0.0 {
+        a / (a + b)
     } else {
-        let k = 0.5 * (ray.direction.y + 1.0);
-        ColorRGB::new(1.0, 1.0, 1.0) * (1.0 - k) + ColorRGB::new(0.5, 0.7, 1.0) * k
+        0.0
     }
 }
 
+/// Next event estimation, light reaching the hit point directly from
+/// a shadow ray shot towards a random point of the lights
+///
+fn sample_light<T, R>(
+    ray: &Ray,
+    hit: &HitRecord,
+    world: &T,
+    lights: &LightList,
+    rng: &mut R,
+) -> ColorRGB
+where
+    T: Hittable + 'static,
+    R: Rng + ?Sized,
+{
+    let direction = lights.sample(&hit.point, rng);
+    let light_pdf = lights.pdf(&hit.point, &direction);
+    let scattering = Material::scattering(ray, hit, &direction);
+    if light_pdf <= 0.0 || scattering.is_near_zero() {
+        return ColorRGB::zero();
+    }
+
+    // any emitter the shadow ray hits first is counted, occluders emit nothing
+    let shadow_ray = Ray::new(hit.point, direction).with_time(ray.time);
+    match world.hit(&shadow_ray, 1E-3, f32::INFINITY) {
+        Some(light_hit) => {
+            let weight = power_heuristic(light_pdf, Material::scattering_pdf(ray, hit, &direction));
+            scattering * Material::emitted(&light_hit) * (weight / light_pdf)
+        }
+        None => ColorRGB::zero(),
+    }
+}
+
+/// Multiple importance sampling of the lights and the material, the bounce
+/// direction is taken from either of them with equal probability and weighted
+/// by the mixture of both densities so that neither small lights nor glossy
+/// reflections of large lights are too noisy
+/// https://raytracing.github.io/books/RayTracingTheRestOfYourLife.html#mixturedensities
+///
+fn mix_light_sampling<R>(
+    ray: &Ray,
+    hit: &HitRecord,
+    bounce: HitBounce,
+    lights: &LightList,
+    rng: &mut R,
+) -> Option<HitBounce>
+where
+    R: Rng + ?Sized,
+{
+    let direction = if rng.gen::<bool>() {
+        lights.sample(&hit.point, rng)
+    } else {
+        bounce.ray.direction
+    };
+    let pdf = 0.5 * lights.pdf(&hit.point, &direction)
+        + 0.5 * Material::scattering_pdf(ray, hit, &direction);
+    if pdf <= 0.0 {
+        return None;
+    }
+    Some(HitBounce {
+        ray: Ray::new(hit.point, direction).with_time(ray.time),
+        attenuation: Material::scattering(ray, hit, &direction) * (1.0 / pdf),
+        pdf: Some(pdf),
+    })
+}
+
+/// Random generator for the image row j, rows get independent
+/// streams so the result does not depend on which thread renders them
+///
+fn row_rng(seed: u64, j: u32) -> Xoshiro256PlusPlus {
+    Xoshiro256PlusPlus::seed_from_u64(seed ^ (j as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// Random generator for the image row j in the given progressive pass
+///
+fn pass_rng(seed: u64, pass: u32, j: u32) -> Xoshiro256PlusPlus {
+    row_rng(
+        seed.wrapping_add((pass as u64).wrapping_mul(0xBF58_476D_1CE4_E5B9)),
+        j,
+    )
+}
+
+fn thread_pool(settings: &RenderSettings) -> rayon::ThreadPool {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(settings.threads)
+        .build()
+        .expect("Unable to create render thread pool")
+}
+
 /// Shoot a ray through every image pixel from the camera and accumulate
-/// their colors into an image
+/// their average linear radiance into an image, image rows are rendered concurrently
+/// on `settings.threads` threads each with its own random generator,
+/// returns the number of samples taken for every pixel
 ///
-pub fn color_image<T>(image: &mut Image, camera: impl Camera, world: T, settings: RenderSettings)
+pub fn color_image<T>(
+    image: &mut Image,
+    camera: impl Camera,
+    world: T,
+    settings: RenderSettings,
+) -> SampleCounts
 where
     T: Hittable + 'static,
 {
-    let bar = ProgressBar::new((image.height).into()).with_style(
-        ProgressStyle::with_template(
-            "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}",
-        )
-        .unwrap(),
-    );
-
-    for j in 0..image.height {
-        for i in 0..image.width {
-            let mut color = ColorRGB::default();
-            for _ in 0..settings.samples_per_px {
-                // find normalzed coordsinates + random deviation and ray through them
-                let (u, v) = image.pixel_to_uv_noisy(i, j);
-                let ray = camera.ray_from_uv(u, v);
-
-                // decide on color depending on the world properties
-                color += collect_color(&ray, &world, settings.bounce_depth);
-            }
-            color = correct_gamma(color, settings.samples_per_px);
-            image.set_at(i, j, color);
+    let progress = RenderProgress::new(image.height.into(), settings.progress, "rows");
+    let pool = thread_pool(&settings);
+    let seed = settings.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let rows: Vec<Vec<(ColorRGB, u32)>> = pool.install(|| {
+        (0..image.height)
+            .into_par_iter()
+            .map(|j| {
+                let mut rng = row_rng(seed, j);
+                let row = render_row(image, j, &camera, &world, &settings, &mut rng);
+
+                // row finished
+                progress.inc();
+                row
+            })
+            .collect()
+    });
+    progress.finish();
+
+    // merge rendered rows into the image buffer
+    let mut counts = SampleCounts::new(image.width, image.height);
+    for (j, row) in rows.into_iter().enumerate() {
+        for (i, (color, count)) in row.into_iter().enumerate() {
+            image.set_at(i as u32, j as u32, color);
+            counts.set_at(i as u32, j as u32, count);
         }
+    }
+    image.tone_mapping = settings.tone_mapping;
+    counts
+}
 
-        // row finished
-        bar.inc(1);
+/// Render the image progressively in `settings.samples_per_px` passes of one
+/// sample per pixel, after every pass the image holds the running average of
+/// the accumulated samples and `on_pass` is called with it and the number of
+/// finished passes, e.g. to show or save a preview
+///
+pub fn color_image_progressive<T, F>(
+    image: &mut Image,
+    camera: impl Camera,
+    world: T,
+    settings: RenderSettings,
+    mut on_pass: F,
+) where
+    T: Hittable + 'static,
+    F: FnMut(&Image, u32),
+{
+    let progress = RenderProgress::new(settings.samples_per_px.into(), settings.progress, "passes");
+    let pool = thread_pool(&settings);
+    let seed = settings.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let mut accumulator = Accumulator::new(image.width, image.height);
+    image.tone_mapping = settings.tone_mapping;
+
+    for pass in 0..settings.samples_per_px {
+        let image_ref: &Image = image;
+        let rows: Vec<Vec<ColorRGB>> = pool.install(|| {
+            (0..image_ref.height)
+                .into_par_iter()
+                .map(|j| {
+                    let mut rng = pass_rng(seed, pass, j);
+                    (0..image_ref.width)
+                        .map(|i| {
+                            sample_pixel(image_ref, i, j, &camera, &world, &settings, &mut rng)
+                        })
+                        .collect()
+                })
+                .collect()
+        });
+        accumulator.add_pass(rows);
+        accumulator.write_average(image);
+        progress.inc();
+        on_pass(image, accumulator.passes());
     }
-    bar.finish();
+    progress.finish();
+}
+
+/// Radiance carried by a single random ray through the pixel (i, j)
+///
+fn sample_pixel<T, R>(
+    image: &Image,
+    i: u32,
+    j: u32,
+    camera: &impl Camera,
+    world: &T,
+    settings: &RenderSettings,
+    rng: &mut R,
+) -> ColorRGB
+where
+    T: Hittable + 'static,
+    R: Rng,
+{
+    // find normalzed coordsinates + random deviation and ray through them
+    let (u, v) = image.pixel_to_uv_noisy(i, j, rng);
+    let ray = camera.sample_ray(u, v, rng);
+
+    // decide on color depending on the world properties
+    collect_color(&ray, world, settings, settings.bounce_depth, rng)
 }
 
-// Apply gamma=2.0 correction + ensure the color values dont go outside the bounds
-// https://raytracing.github.io/books/RayTracingInOneWeekend.html#diffusematerials/usinggammacorrectionforaccuratecolorintensity
-//
-fn correct_gamma(color: ColorRGB, samples_per_px: u32) -> ColorRGB {
-    let scale = 1.0 / samples_per_px as f32;
-    ColorRGB {
-        x: (scale * color.x).sqrt().clamp(0.0, 0.999),
-        y: (scale * color.y).sqrt().clamp(0.0, 0.999),
-        z: (scale * color.z).sqrt().clamp(0.0, 0.999),
+/// Render colors of all pixels in the image row j
+/// together with the number of samples taken for them
+///
+fn render_row<T, R>(
+    image: &Image,
+    j: u32,
+    camera: &impl Camera,
+    world: &T,
+    settings: &RenderSettings,
+    rng: &mut R,
+) -> Vec<(ColorRGB, u32)>
+where
+    T: Hittable + 'static,
+    R: Rng,
+{
+    (0..image.width)
+        .map(|i| match &settings.adaptive {
+            Some(adaptive) => {
+                let mut estimate = PixelEstimate::default();
+                while !estimate.is_done(adaptive) {
+                    estimate.add(sample_pixel(image, i, j, camera, world, settings, rng));
+                }
+                (estimate.mean(), estimate.count())
+            }
+            None => {
+                let mut color = ColorRGB::default();
+                for _ in 0..settings.samples_per_px {
+                    color += sample_pixel(image, i, j, camera, world, settings, rng);
+                }
+                (
+                    color * (1.0 / settings.samples_per_px as f32),
+                    settings.samples_per_px,
+                )
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+    use std::sync::Arc;
+
+    fn render(seed: Option<u64>, threads: usize) -> Image {
+        let mut scene = SphereScene::new();
+        scene.add(Sphere::new(
+            Vector3D::new(0.0, 0.0, -1.0),
+            0.5,
+            Material::Dielectric(1.5),
+        ));
+        scene.add(Sphere::new(
+            Vector3D::new(0.0, -100.5, -1.0),
+            100.0,
+            Material::Lambertan(ColorRGB::new(0.5, 0.5, 0.5).into()),
+        ));
+        let camera = FovCamera::new(
+            Vector3D::zero(),
+            -Vector3D::unit_z(),
+            Vector3D::unit_y(),
+            60.0,
+            1.5,
+        );
+        let settings = RenderSettings {
+            samples_per_px: 4,
+            threads,
+            progress: ProgressMode::Hidden,
+            seed,
+            ..Default::default()
+        };
+        let mut image = Image::new(12, 8);
+        color_image(&mut image, camera, scene, settings);
+        image
+    }
+
+    fn pixels(image: &Image) -> Vec<ColorRGB> {
+        (0..image.height)
+            .flat_map(|j| (0..image.width).map(move |i| image.at(i, j)))
+            .collect()
+    }
+
+    #[test]
+    fn test_collect_emitted_light() {
+        let light = Material::DiffuseLight(ColorRGB::new(1.0, 0.5, 0.25), 4.0);
+        let sphere = Sphere::new(Vector3D::new(0.0, 0.0, -2.0), 0.5, light);
+        let settings = RenderSettings {
+            environment: Environment::Solid(ColorRGB::zero()),
+            ..Default::default()
+        };
+        let mut rng = row_rng(0, 0);
+
+        let ray = Ray::new(Vector3D::zero(), -Vector3D::unit_z());
+        let color = collect_color(&ray, &sphere, &settings, 5, &mut rng);
+        assert_vec_eq(&color, &ColorRGB::new(4.0, 2.0, 1.0));
+
+        let ray = Ray::new(Vector3D::zero(), Vector3D::unit_z());
+        let color = collect_color(&ray, &sphere, &settings, 5, &mut rng);
+        assert_vec_eq(&color, &ColorRGB::zero());
+    }
+
+    /// mean and variance of the light reflected by a diffuse floor
+    /// lit by a small sphere light
+    ///
+    fn small_light_estimate(light_sampling: LightSampling) -> (f32, f32) {
+        let light = Arc::new(Sphere::new(
+            Vector3D::new(0.0, 2.0, 0.0),
+            0.1,
+            Material::DiffuseLight(ColorRGB::new(1.0, 1.0, 1.0), 50.0),
+        ));
+        let floor = Plane::new(
+            Vector3D::zero(),
+            Vector3D::unit_y(),
+            Material::Lambertan(ColorRGB::new(0.5, 0.5, 0.5).into()),
+        );
+        let mut world = HittableScene::new();
+        world.add_light(light);
+        world.add(Arc::new(floor));
+        let settings = RenderSettings {
+            environment: Environment::Solid(ColorRGB::zero()),
+            light_sampling,
+            ..Default::default()
+        };
+
+        let mut rng = row_rng(0, 0);
+        let ray = Ray::new(Vector3D::new(0.0, 1.0, 1.0), Vector3D::new(0.0, -1.0, -1.0));
+        let count = 20000;
+        let samples: Vec<f32> = (0..count)
+            .map(|_| collect_color(&ray, &world, &settings, 2, &mut rng).x)
+            .collect();
+        let mean = samples.iter().sum::<f32>() / count as f32;
+        let variance = samples.iter().map(|x| (x - mean) * (x - mean)).sum::<f32>() / count as f32;
+        (mean, variance)
+    }
+
+    #[test]
+    fn test_light_sampling_reduces_noise() {
+        // irradiance of the small sphere, E = L * PI * r^2 / d^2 with cos = 1
+        let expected = 0.5 / PI * 50.0 * PI * 0.01 / 4.0;
+
+        let (mean, variance) = small_light_estimate(LightSampling::None);
+        assert!((mean - expected).abs() < 0.3 * expected);
+        for sampling in [LightSampling::Mixture, LightSampling::NextEvent] {
+            let (sampled_mean, sampled_variance) = small_light_estimate(sampling);
+            assert!((sampled_mean - expected).abs() < 0.05 * expected);
+            assert!(sampled_variance * 10.0 < variance);
+        }
+    }
+
+    #[test]
+    fn test_next_event_shadows() {
+        // a large blocker between the floor and the light
+        let mut world = HittableScene::new();
+        world.add_light(Arc::new(XzRect::new(
+            [-0.5, 0.5],
+            [-0.5, 0.5],
+            3.0,
+            Material::DiffuseLight(ColorRGB::new(1.0, 1.0, 1.0), 10.0),
+        )));
+        world.add(Arc::new(XzRect::new(
+            [-5.0, 5.0],
+            [-5.0, 5.0],
+            2.0,
+            Material::Lambertan(ColorRGB::zero().into()),
+        )));
+        world.add(Arc::new(Plane::new(
+            Vector3D::zero(),
+            Vector3D::unit_y(),
+            Material::Lambertan(ColorRGB::new(0.5, 0.5, 0.5).into()),
+        )));
+        let settings = RenderSettings {
+            environment: Environment::Solid(ColorRGB::zero()),
+            ..Default::default()
+        };
+        assert_eq!(settings.light_sampling, LightSampling::NextEvent);
+
+        let mut rng = row_rng(0, 0);
+        let ray = Ray::new(Vector3D::new(0.0, 1.0, 1.0), Vector3D::new(0.0, -1.0, -1.0));
+        for _ in 0..100 {
+            let color = collect_color(&ray, &world, &settings, 5, &mut rng);
+            assert_vec_eq(&color, &ColorRGB::zero());
+        }
+    }
+
+    #[test]
+    fn test_same_seed_renders_identical_image() {
+        let single = pixels(&render(Some(42), 1));
+        let multi = pixels(&render(Some(42), 3));
+        assert_eq!(single, multi);
+        assert_eq!(single, pixels(&render(Some(42), 1)));
+    }
+
+    #[test]
+    fn test_different_seed_renders_different_image() {
+        assert_ne!(pixels(&render(Some(1), 1)), pixels(&render(Some(2), 1)));
+    }
+
+    #[test]
+    fn test_adaptive_sampling_focuses_on_noise() {
+        // the sphere lit by the sky gradient is noisy, the sky itself is smooth
+        let world = Sphere::new(
+            Vector3D::new(0.0, 0.0, -2.0),
+            0.5,
+            Material::Lambertan(ColorRGB::new(0.5, 0.5, 0.5).into()),
+        );
+        let camera = FovCamera::new(
+            Vector3D::zero(),
+            -Vector3D::unit_z(),
+            Vector3D::unit_y(),
+            60.0,
+            1.0,
+        );
+        let adaptive = AdaptiveSampling {
+            min_samples: 4,
+            max_samples: 64,
+            threshold: 0.05,
+        };
+        let settings = RenderSettings {
+            adaptive: Some(adaptive),
+            progress: ProgressMode::Hidden,
+            seed: Some(3),
+            environment: Environment::Gradient {
+                bottom: ColorRGB::zero(),
+                top: ColorRGB::new(1.0, 1.0, 1.0),
+            },
+            ..Default::default()
+        };
+        let mut image = Image::new(9, 9);
+        let counts = color_image(&mut image, camera, world, settings);
+
+        assert_eq!(counts.dims(), (9, 9));
+        assert_eq!(counts.at(0, 0), adaptive.min_samples);
+        assert!(counts.at(4, 4) > 4 * adaptive.min_samples);
+        for j in 0..9 {
+            for i in 0..9 {
+                let count = counts.at(i, j);
+                assert!(count >= adaptive.min_samples && count <= adaptive.max_samples);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fixed_sampling_counts() {
+        let mut image = Image::new(3, 2);
+        let settings = RenderSettings {
+            samples_per_px: 5,
+            progress: ProgressMode::Hidden,
+            seed: Some(1),
+            ..Default::default()
+        };
+        let camera = FovCamera::new(
+            Vector3D::zero(),
+            -Vector3D::unit_z(),
+            Vector3D::unit_y(),
+            60.0,
+            1.5,
+        );
+        let counts = color_image(&mut image, camera, SphereScene::new(), settings);
+        assert_eq!(counts.total(), 30);
+    }
+
+    #[test]
+    fn test_progressive_running_average() {
+        let render = |threads| {
+            let world = Sphere::new(
+                Vector3D::new(0.0, 0.0, -1.0),
+                0.5,
+                Material::Lambertan(ColorRGB::new(0.5, 0.5, 0.5).into()),
+            );
+            let camera = FovCamera::new(
+                Vector3D::zero(),
+                -Vector3D::unit_z(),
+                Vector3D::unit_y(),
+                60.0,
+                1.5,
+            );
+            let settings = RenderSettings {
+                samples_per_px: 3,
+                threads,
+                progress: ProgressMode::Hidden,
+                seed: Some(7),
+                ..Default::default()
+            };
+            let mut snapshots = Vec::new();
+            let mut image = Image::new(6, 4);
+            color_image_progressive(&mut image, camera, world, settings, |image, passes| {
+                snapshots.push((passes, pixels(image)));
+            });
+            (pixels(&image), snapshots)
+        };
+
+        let (final_pixels, snapshots) = render(1);
+        let passes: Vec<u32> = snapshots.iter().map(|(n, _)| *n).collect();
+        assert_eq!(passes, vec![1, 2, 3]);
+        assert_eq!(snapshots[2].1, final_pixels);
+        // every pass refines the preview of the noisy sphere
+        assert_ne!(snapshots[0].1, snapshots[1].1);
+        assert_ne!(snapshots[1].1, snapshots[2].1);
+
+        // passes are deterministic regardless of the thread count
+        assert_eq!(render(3).0, final_pixels);
     }
 }
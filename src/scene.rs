@@ -0,0 +1,1453 @@
+use crate::prelude::*;
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Errors that can occur while loading a render job
+///
+#[derive(Debug)]
+pub enum SceneError {
+    /// job file could not be read
+    Io(std::io::Error),
+    /// job file is not a valid json or misses required fields
+    Parse(serde_json::Error),
+    /// material type is not one of the known materials
+    UnknownMaterial(String),
+    /// optional in json but required field is missing
+    MissingField(String),
+    /// camera coordinate system can not be built
+    DegenerateCamera(String),
+    /// value is outside of its valid range
+    InvalidValue(String),
+    /// image referenced by the job could not be loaded
+    Image(PathBuf, image::ImageError),
+    /// model referenced by the job could not be loaded
+    Model(PathBuf, ObjError),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "unable to read job file: {}", e),
+            SceneError::Parse(e) => write!(f, "invalid job description: {}", e),
+            SceneError::UnknownMaterial(name) => write!(
+                f,
+                "unknown material `{}`, expected one of {}",
+                name,
+                MATERIAL_NAMES.join(", ")
+            ),
+            SceneError::MissingField(field) => write!(f, "missing field `{}`", field),
+            SceneError::DegenerateCamera(reason) => write!(f, "degenerate camera: {}", reason),
+            SceneError::InvalidValue(reason) => write!(f, "invalid value: {}", reason),
+            SceneError::Image(path, e) => {
+                write!(f, "unable to load image {}: {}", path.display(), e)
+            }
+            SceneError::Model(path, e) => {
+                write!(f, "unable to load model {}: {}", path.display(), e)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneError::Io(e) => Some(e),
+            SceneError::Parse(e) => Some(e),
+            SceneError::Image(_, e) => Some(e),
+            SceneError::Model(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SceneError {
+    fn from(e: std::io::Error) -> Self {
+        SceneError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SceneError {
+    fn from(e: serde_json::Error) -> Self {
+        SceneError::Parse(e)
+    }
+}
+
+/// Everything needed to render a single image
+///
+pub struct Job {
+    pub camera: ShutterCamera<FovCamera>,
+    pub image: Image,
+    pub scene: HittableScene,
+    pub settings: RenderSettings,
+}
+
+impl Job {
+    /// build a render job from its json description, relative paths
+    /// in the description are resolved from the current directory
+    ///
+    pub fn from_json(json: &str) -> Result<Self, SceneError> {
+        Self::from_json_in(json, Path::new(""))
+    }
+
+    /// read and build a render job from a json file, relative paths
+    /// in the description are resolved from the file directory
+    ///
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, SceneError> {
+        let json = std::fs::read_to_string(&path)?;
+        let base_dir = path.as_ref().parent().unwrap_or_else(|| Path::new(""));
+        Self::from_json_in(&json, base_dir)
+    }
+
+    fn from_json_in(json: &str, base_dir: &Path) -> Result<Self, SceneError> {
+        let desc: JobDesc = serde_json::from_str(json)?;
+        desc.build(base_dir)
+    }
+}
+
+const MATERIAL_NAMES: [&str; 7] = [
+    "none",
+    "lambertan",
+    "metal",
+    "dielectric",
+    "diffuse_light",
+    "isotropic",
+    "pbr",
+];
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JobDesc {
+    camera: CameraDesc,
+    image: ImageDesc,
+    scene: Vec<BodyDesc>,
+    #[serde(default)]
+    renderer: RendererDesc,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    origin: [f32; 3],
+    lookat: [f32; 3],
+    vup: [f32; 3],
+    vfov: f32,
+    /// taken from the image dimentions if not set
+    aspect_ratio: Option<f32>,
+    /// `[open, close]` times of the shutter, closed at 0 if not set
+    shutter: Option<[f32; 2]>,
+    /// lens diameter, pinhole camera if not set
+    #[serde(default)]
+    aperture: f32,
+    /// distance to `lookat` if not set
+    focus_distance: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ImageDesc {
+    width: u32,
+    height: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase", deny_unknown_fields)]
+enum BodyDesc {
+    Sphere {
+        center: [f32; 3],
+        radius: f32,
+        material: MaterialDesc,
+    },
+    /// sphere moving from `center0` at `time0` to `center1` at `time1`
+    #[serde(rename = "moving_sphere")]
+    MovingSphere {
+        center0: [f32; 3],
+        center1: [f32; 3],
+        #[serde(default)]
+        time0: f32,
+        #[serde(default = "default_time1")]
+        time1: f32,
+        radius: f32,
+        material: MaterialDesc,
+    },
+    Triangle {
+        vertices: [[f32; 3]; 3],
+        /// per vertex normals for smooth shading
+        normals: Option<[[f32; 3]; 3]>,
+        material: MaterialDesc,
+    },
+    Mesh {
+        vertices: Vec<[f32; 3]>,
+        /// per vertex normals for smooth shading
+        #[serde(default)]
+        normals: Vec<[f32; 3]>,
+        indices: Vec<[u32; 3]>,
+        material: MaterialDesc,
+    },
+    Plane {
+        point: [f32; 3],
+        normal: [f32; 3],
+        material: MaterialDesc,
+    },
+    #[serde(rename = "xy_rect")]
+    XyRect {
+        x: [f32; 2],
+        y: [f32; 2],
+        k: f32,
+        material: MaterialDesc,
+    },
+    #[serde(rename = "xz_rect")]
+    XzRect {
+        x: [f32; 2],
+        z: [f32; 2],
+        k: f32,
+        material: MaterialDesc,
+    },
+    #[serde(rename = "yz_rect")]
+    YzRect {
+        y: [f32; 2],
+        z: [f32; 2],
+        k: f32,
+        material: MaterialDesc,
+    },
+    /// axis aligned box between two opposite corners
+    Cuboid {
+        min: [f32; 3],
+        max: [f32; 3],
+        material: MaterialDesc,
+    },
+    /// Wavefront OBJ model with materials from its MTL libraries
+    Obj {
+        path: PathBuf,
+        #[serde(default = "default_scale")]
+        scale: f32,
+        /// rotation around Y axis in degrees
+        #[serde(default)]
+        rotate_y: f32,
+        #[serde(default)]
+        translate: [f32; 3],
+    },
+    /// fog or smoke filling the boundary body
+    #[serde(rename = "constant_medium")]
+    ConstantMedium {
+        boundary: Box<BodyDesc>,
+        density: f32,
+        /// white if not set
+        albedo: Option<TextureDesc>,
+    },
+    /// bodies scaled, then rotated around X, Y and Z axes
+    /// in degrees and then translated
+    Transform {
+        bodies: Vec<BodyDesc>,
+        #[serde(default = "default_scale_xyz")]
+        scale: [f32; 3],
+        #[serde(default)]
+        rotate: [f32; 3],
+        #[serde(default)]
+        translate: [f32; 3],
+    },
+}
+
+fn default_time1() -> f32 {
+    1.0
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+fn default_scale_xyz() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+/// material is either just a name with default parameters
+/// or an object with a `type` name and parameters
+///
+enum MaterialDesc {
+    Name(String),
+    Params(MaterialParams),
+}
+
+/// untagged enums hide the reason why the parameters do not match,
+/// e.g. a misspelled field, behind a generic error
+///
+impl<'de> Deserialize<'de> for MaterialDesc {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(name) => Ok(MaterialDesc::Name(name)),
+            value => MaterialParams::deserialize(value)
+                .map(MaterialDesc::Params)
+                .map_err(D::Error::custom),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct MaterialParams {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    #[serde(alias = "base_color")]
+    albedo: Option<TextureDesc>,
+    fuzz: Option<f32>,
+    refraction_index: Option<f32>,
+    color: Option<[f32; 3]>,
+    intensity: Option<f32>,
+    metallic: Option<f32>,
+    roughness: Option<f32>,
+}
+
+/// texture is either a plain color or an object describing a pattern
+///
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TextureDesc {
+    Color([f32; 3]),
+    Pattern(PatternDesc),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase", deny_unknown_fields)]
+enum PatternDesc {
+    Checker {
+        even: Box<TextureDesc>,
+        odd: Box<TextureDesc>,
+        #[serde(default = "default_scale")]
+        scale: f32,
+    },
+    /// path to the image wrapped around the surface
+    Image(PathBuf),
+    Noise {
+        #[serde(default)]
+        seed: u64,
+        #[serde(default)]
+        pattern: NoisePatternDesc,
+        #[serde(default = "default_scale")]
+        scale: f32,
+        /// number of turbulence octaves
+        #[serde(default = "default_noise_depth")]
+        depth: u32,
+        color: Option<[f32; 3]>,
+    },
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+enum NoisePatternDesc {
+    #[default]
+    Smooth,
+    Turbulence,
+    Marble,
+}
+
+fn default_noise_depth() -> u32 {
+    7
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct RendererDesc {
+    samples_per_px: Option<u32>,
+    adaptive: Option<AdaptiveDesc>,
+    bounce_depth: Option<u32>,
+    threads: Option<usize>,
+    seed: Option<u64>,
+    /// sky gradient if not set
+    environment: Option<EnvironmentDesc>,
+    light_sampling: Option<LightSamplingDesc>,
+    #[serde(default)]
+    tone_mapping: ToneMappingDesc,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AdaptiveDesc {
+    min_samples: Option<u32>,
+    max_samples: Option<u32>,
+    threshold: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum LightSamplingDesc {
+    None,
+    Mixture,
+    NextEvent,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct ToneMappingDesc {
+    /// in stops
+    #[serde(default)]
+    exposure: f32,
+    #[serde(default)]
+    curve: ToneCurveDesc,
+    /// gamma of a plain power transfer, sRGB if not set
+    gamma: Option<f32>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+enum ToneCurveDesc {
+    #[default]
+    Clamp,
+    Reinhard,
+    Filmic,
+    Aces,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase", deny_unknown_fields)]
+enum EnvironmentDesc {
+    Sky,
+    Solid([f32; 3]),
+    Gradient {
+        bottom: [f32; 3],
+        top: [f32; 3],
+    },
+    /// path to the equirectangular map image
+    Map(PathBuf),
+}
+
+impl JobDesc {
+    fn build(self, base_dir: &Path) -> Result<Job, SceneError> {
+        let image = self.image.build()?;
+        let aspect_ratio = self
+            .camera
+            .aspect_ratio
+            .unwrap_or(image.width as f32 / image.height as f32);
+        let camera = self.camera.build(aspect_ratio)?;
+
+        let mut scene = HittableScene::new();
+        for body in self.scene {
+            body.add_to(&mut scene, base_dir)?;
+        }
+
+        Ok(Job {
+            camera,
+            image,
+            scene,
+            settings: self.renderer.build(base_dir)?,
+        })
+    }
+}
+
+impl CameraDesc {
+    fn build(self, aspect_ratio: f32) -> Result<ShutterCamera<FovCamera>, SceneError> {
+        let origin = Vector3D::from(self.origin);
+        let lookat = Vector3D::from(self.lookat);
+        let vup = Vector3D::from(self.vup);
+
+        let view = lookat - origin;
+        if view.is_near_zero() {
+            return Err(SceneError::DegenerateCamera(
+                "`lookat` coincides with `origin`".to_string(),
+            ));
+        }
+        if vup.is_near_zero() {
+            return Err(SceneError::DegenerateCamera(
+                "`vup` is a zero vector".to_string(),
+            ));
+        }
+        if vup.unit().cross(&view.unit()).is_near_zero() {
+            return Err(SceneError::DegenerateCamera(
+                "`vup` is parallel to the view direction".to_string(),
+            ));
+        }
+        if !(self.vfov > 0.0 && self.vfov < 180.0) {
+            return Err(SceneError::DegenerateCamera(format!(
+                "`vfov` {} is outside of (0, 180) degrees",
+                self.vfov
+            )));
+        }
+        if !(aspect_ratio > 0.0 && aspect_ratio.is_finite()) {
+            return Err(SceneError::DegenerateCamera(format!(
+                "`aspect_ratio` {} is not positive",
+                aspect_ratio
+            )));
+        }
+
+        let shutter = match self.shutter {
+            Some([open, close]) if close < open => {
+                return Err(SceneError::DegenerateCamera(format!(
+                    "`shutter` closes at {} before it opens at {}",
+                    close, open
+                )));
+            }
+            Some([open, close]) => Shutter::new(open, close),
+            None => Shutter::default(),
+        };
+
+        if !(self.aperture >= 0.0 && self.aperture.is_finite()) {
+            return Err(SceneError::DegenerateCamera(format!(
+                "`aperture` {} is negative",
+                self.aperture
+            )));
+        }
+        let focus_distance = self.focus_distance.unwrap_or_else(|| view.norm());
+        if !(focus_distance > 0.0 && focus_distance.is_finite()) {
+            return Err(SceneError::DegenerateCamera(format!(
+                "`focus_distance` {} is not positive",
+                focus_distance
+            )));
+        }
+
+        Ok(FovCamera::new(origin, lookat, vup, self.vfov, aspect_ratio)
+            .with_focus(self.aperture, focus_distance)
+            .with_shutter(shutter))
+    }
+}
+
+impl ImageDesc {
+    fn build(self) -> Result<Image, SceneError> {
+        if self.width < 2 || self.height < 2 {
+            return Err(SceneError::InvalidValue(format!(
+                "image size {}x{} is smaller than 2x2",
+                self.width, self.height
+            )));
+        }
+        Ok(Image::new(self.width, self.height))
+    }
+}
+
+impl BodyDesc {
+    fn add_to(self, scene: &mut HittableScene, base_dir: &Path) -> Result<(), SceneError> {
+        match self {
+            BodyDesc::Sphere {
+                center,
+                radius,
+                material,
+            } => {
+                let material = material.build(base_dir)?;
+                add_body(scene, material, |m| Sphere::new(center.into(), radius, m));
+            }
+            BodyDesc::MovingSphere {
+                center0,
+                center1,
+                time0,
+                time1,
+                radius,
+                material,
+            } => {
+                let material = material.build(base_dir)?;
+                if matches!(material, Material::DiffuseLight(..)) {
+                    return Err(SceneError::InvalidValue(
+                        "moving sphere `diffuse_light` can not be sampled as a light".to_string(),
+                    ));
+                }
+                scene.add(Arc::new(MovingSphere::new(
+                    center0.into(),
+                    center1.into(),
+                    time0,
+                    time1,
+                    radius,
+                    material,
+                )));
+            }
+            BodyDesc::Triangle {
+                vertices: [p0, p1, p2],
+                normals,
+                material,
+            } => {
+                let material = material.build(base_dir)?;
+                let mut triangle = Triangle::new(p0.into(), p1.into(), p2.into(), material);
+                if let Some([n0, n1, n2]) = normals {
+                    let normals = [n0, n1, n2].map(|n| Vector3D::from(n).unit());
+                    triangle = triangle.with_normals(normals);
+                }
+                scene.add(Arc::new(triangle));
+            }
+            BodyDesc::Mesh {
+                vertices,
+                normals,
+                indices,
+                material,
+            } => {
+                if let Some(index) = indices
+                    .iter()
+                    .flatten()
+                    .find(|&&i| i as usize >= vertices.len())
+                {
+                    return Err(SceneError::InvalidValue(format!(
+                        "mesh index {} is out of {} vertices",
+                        index,
+                        vertices.len()
+                    )));
+                }
+                if !normals.is_empty() && normals.len() != vertices.len() {
+                    return Err(SceneError::InvalidValue(format!(
+                        "mesh has {} normals for {} vertices",
+                        normals.len(),
+                        vertices.len()
+                    )));
+                }
+                let material = material.build(base_dir)?;
+                let vertices = vertices.into_iter().map(Vector3D::from).collect();
+                let normals = normals
+                    .into_iter()
+                    .map(|n| Vector3D::from(n).unit())
+                    .collect();
+                scene.add(Arc::new(Mesh::with_normals(
+                    vertices, normals, indices, material,
+                )));
+            }
+            BodyDesc::Plane {
+                point,
+                normal,
+                material,
+            } => {
+                if Vector3D::from(normal).is_near_zero() {
+                    return Err(SceneError::InvalidValue(
+                        "plane `normal` is a zero vector".to_string(),
+                    ));
+                }
+                let material = material.build(base_dir)?;
+                scene.add(Arc::new(Plane::new(point.into(), normal.into(), material)));
+            }
+            BodyDesc::XyRect { x, y, k, material } => {
+                let material = material.build(base_dir)?;
+                add_body(scene, material, |m| XyRect::new(x, y, k, m));
+            }
+            BodyDesc::XzRect { x, z, k, material } => {
+                let material = material.build(base_dir)?;
+                add_body(scene, material, |m| XzRect::new(x, z, k, m));
+            }
+            BodyDesc::YzRect { y, z, k, material } => {
+                let material = material.build(base_dir)?;
+                add_body(scene, material, |m| YzRect::new(y, z, k, m));
+            }
+            BodyDesc::Cuboid { min, max, material } => {
+                let material = material.build(base_dir)?;
+                scene.add(Arc::new(Cuboid::new(min.into(), max.into(), material)));
+            }
+            BodyDesc::Obj {
+                path,
+                scale,
+                rotate_y,
+                translate,
+            } => {
+                let placement = Placement {
+                    scale,
+                    rotate_y,
+                    translate: translate.into(),
+                };
+                // bad placements are not a problem of the model file
+                placement
+                    .transform()
+                    .map_err(|e| SceneError::InvalidValue(format!("model {}", e)))?;
+                let path = base_dir.join(path);
+                let meshes = load_obj(&path, &placement).map_err(|e| SceneError::Model(path, e))?;
+                for mesh in meshes {
+                    scene.add(Arc::new(mesh));
+                }
+            }
+            BodyDesc::ConstantMedium {
+                boundary,
+                density,
+                albedo,
+            } => {
+                if !(density > 0.0 && density.is_finite()) {
+                    return Err(SceneError::InvalidValue(format!(
+                        "medium `density` {} is not positive",
+                        density
+                    )));
+                }
+                let albedo = match albedo {
+                    Some(albedo) => albedo.build(base_dir)?,
+                    None => ColorRGB::new(1.0, 1.0, 1.0).into(),
+                };
+                let mut group = HittableScene::new();
+                boundary.add_to(&mut group, base_dir)?;
+                if group.lights().is_some_and(|lights| !lights.is_empty()) {
+                    return Err(SceneError::InvalidValue(
+                        "medium `boundary` emits light hidden by the medium".to_string(),
+                    ));
+                }
+                scene.add(Arc::new(ConstantMedium::new(group, density, albedo)));
+            }
+            BodyDesc::Transform {
+                bodies,
+                scale,
+                rotate: [x, y, z],
+                translate,
+            } => {
+                if scale.contains(&0.0) {
+                    return Err(SceneError::InvalidValue(format!(
+                        "transform `scale` {:?} has a zero factor",
+                        scale
+                    )));
+                }
+                let transform = Transform::scale(scale.into())
+                    .then(&Transform::rotate_x(x))
+                    .then(&Transform::rotate_y(y))
+                    .then(&Transform::rotate_z(z))
+                    .then(&Transform::translate(translate.into()));
+                let mut group = HittableScene::new();
+                for body in bodies {
+                    body.add_to(&mut group, base_dir)?;
+                }
+                // emitters of the group are sampled in world space through the transform
+                let emits = group.lights().is_some_and(|lights| !lights.is_empty());
+                let group = Arc::new(Transformed::new(group, transform));
+                if emits {
+                    scene.add_light(group);
+                } else {
+                    scene.add(group);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// add a sphere or a rectangle to the scene, emitting ones are also
+/// registered as lights sampled by the renderer, emitters of transformed
+/// groups are registered with the group
+///
+fn add_body<T, F>(scene: &mut HittableScene, material: Material, body: F)
+where
+    T: Hittable + Light + 'static,
+    F: FnOnce(Material) -> T,
+{
+    let emits = matches!(material, Material::DiffuseLight(..));
+    let body = Arc::new(body(material));
+    if emits {
+        scene.add_light(body);
+    } else {
+        scene.add(body);
+    }
+}
+
+impl MaterialDesc {
+    fn build(self, base_dir: &Path) -> Result<Material, SceneError> {
+        let params = match self {
+            MaterialDesc::Name(name) => MaterialParams {
+                kind: Some(name),
+                ..Default::default()
+            },
+            MaterialDesc::Params(params) => params,
+        };
+
+        let kind = params
+            .kind
+            .ok_or_else(|| SceneError::MissingField("material.type".to_string()))?;
+        let albedo = match params.albedo {
+            Some(albedo) => albedo.build(base_dir)?,
+            None => ColorRGB::new(0.5, 0.5, 0.5).into(),
+        };
+
+        match kind.as_str() {
+            "none" => Ok(Material::None),
+            "lambertan" | "lambertian" => Ok(Material::Lambertan(albedo)),
+            "metal" => Ok(Material::Metal(albedo, params.fuzz.unwrap_or(0.0))),
+            "dielectric" => Ok(Material::Dielectric(params.refraction_index.unwrap_or(1.5))),
+            "diffuse_light" => Ok(Material::DiffuseLight(
+                params
+                    .color
+                    .map_or(ColorRGB::new(1.0, 1.0, 1.0), ColorRGB::from),
+                params.intensity.unwrap_or(1.0),
+            )),
+            "isotropic" => Ok(Material::Isotropic(albedo)),
+            "pbr" => {
+                let metallic = params.metallic.unwrap_or(0.0);
+                let roughness = params.roughness.unwrap_or(0.5);
+                if !(0.0..=1.0).contains(&metallic) || !(0.0..=1.0).contains(&roughness) {
+                    return Err(SceneError::InvalidValue(format!(
+                        "pbr metallic {} and roughness {} must be between 0 and 1",
+                        metallic, roughness
+                    )));
+                }
+                Ok(Material::Pbr {
+                    base_color: albedo,
+                    metallic,
+                    roughness,
+                })
+            }
+            _ => Err(SceneError::UnknownMaterial(kind)),
+        }
+    }
+}
+
+impl TextureDesc {
+    fn build(self, base_dir: &Path) -> Result<Texture, SceneError> {
+        match self {
+            TextureDesc::Color(color) => Ok(ColorRGB::from(color).into()),
+            TextureDesc::Pattern(PatternDesc::Checker { even, odd, scale }) => {
+                if !(scale > 0.0 && scale.is_finite()) {
+                    return Err(SceneError::InvalidValue(format!(
+                        "checker `scale` {} is not positive",
+                        scale
+                    )));
+                }
+                Ok(Texture::checker(
+                    even.build(base_dir)?,
+                    odd.build(base_dir)?,
+                    scale,
+                ))
+            }
+            TextureDesc::Pattern(PatternDesc::Noise {
+                seed,
+                pattern,
+                scale,
+                depth,
+                color,
+            }) => {
+                if !(scale > 0.0 && scale.is_finite()) {
+                    return Err(SceneError::InvalidValue(format!(
+                        "noise `scale` {} is not positive",
+                        scale
+                    )));
+                }
+                let pattern = match pattern {
+                    NoisePatternDesc::Smooth => NoisePattern::Smooth,
+                    NoisePatternDesc::Turbulence => NoisePattern::Turbulence(depth),
+                    NoisePatternDesc::Marble => NoisePattern::Marble(depth),
+                };
+                let color = color.map_or(ColorRGB::new(1.0, 1.0, 1.0), ColorRGB::from);
+                Ok(Texture::noise(seed, pattern, scale, color))
+            }
+            TextureDesc::Pattern(PatternDesc::Image(path)) => {
+                let path = base_dir.join(path);
+                let image = ImageTexture::open(&path).map_err(|e| SceneError::Image(path, e))?;
+                Ok(Texture::Image(Arc::new(image)))
+            }
+        }
+    }
+}
+
+impl RendererDesc {
+    fn build(self, base_dir: &Path) -> Result<RenderSettings, SceneError> {
+        let default = RenderSettings::default();
+        let environment = match self.environment {
+            Some(environment) => environment.build(base_dir)?,
+            None => default.environment.clone(),
+        };
+        let samples_per_px = self.samples_per_px.unwrap_or(default.samples_per_px);
+        if samples_per_px < 1 {
+            return Err(SceneError::InvalidValue(
+                "samples_per_px must be at least 1".to_string(),
+            ));
+        }
+        Ok(RenderSettings {
+            samples_per_px,
+            adaptive: self.adaptive.map(AdaptiveDesc::build).transpose()?,
+            bounce_depth: self.bounce_depth.unwrap_or(default.bounce_depth),
+            threads: self.threads.unwrap_or(default.threads),
+            seed: self.seed,
+            environment,
+            light_sampling: match self.light_sampling {
+                Some(LightSamplingDesc::None) => LightSampling::None,
+                Some(LightSamplingDesc::Mixture) => LightSampling::Mixture,
+                Some(LightSamplingDesc::NextEvent) => LightSampling::NextEvent,
+                None => default.light_sampling,
+            },
+            tone_mapping: self.tone_mapping.build()?,
+            ..default
+        })
+    }
+}
+
+impl AdaptiveDesc {
+    fn build(self) -> Result<AdaptiveSampling, SceneError> {
+        let default = AdaptiveSampling::default();
+        let adaptive = AdaptiveSampling {
+            min_samples: self.min_samples.unwrap_or(default.min_samples),
+            max_samples: self.max_samples.unwrap_or(default.max_samples),
+            threshold: self.threshold.unwrap_or(default.threshold),
+        };
+        adaptive
+            .validate()
+            .map_err(|e| SceneError::InvalidValue(e.to_string()))?;
+        Ok(adaptive)
+    }
+}
+
+impl ToneMappingDesc {
+    fn build(self) -> Result<ToneMapping, SceneError> {
+        let tone_mapping = ToneMapping {
+            exposure: self.exposure,
+            curve: match self.curve {
+                ToneCurveDesc::Clamp => ToneCurve::Clamp,
+                ToneCurveDesc::Reinhard => ToneCurve::Reinhard,
+                ToneCurveDesc::Filmic => ToneCurve::Filmic,
+                ToneCurveDesc::Aces => ToneCurve::Aces,
+            },
+            transfer: self
+                .gamma
+                .map_or(TransferFunction::Srgb, TransferFunction::Gamma),
+        };
+        tone_mapping
+            .validate()
+            .map_err(|e| SceneError::InvalidValue(e.to_string()))?;
+        Ok(tone_mapping)
+    }
+}
+
+impl EnvironmentDesc {
+    fn build(self, base_dir: &Path) -> Result<Environment, SceneError> {
+        Ok(match self {
+            EnvironmentDesc::Sky => Environment::sky(),
+            EnvironmentDesc::Solid(color) => Environment::Solid(color.into()),
+            EnvironmentDesc::Gradient { bottom, top } => Environment::Gradient {
+                bottom: bottom.into(),
+                top: top.into(),
+            },
+            EnvironmentDesc::Map(path) => {
+                let path = base_dir.join(path);
+                let map = EnvironmentMap::open(&path).map_err(|e| SceneError::Image(path, e))?;
+                Environment::Map(Arc::new(map))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job_with_camera(camera: &str) -> String {
+        format!(
+            r#"{{
+                "camera": {},
+                "image": {{ "width": 30, "height": 20 }},
+                "scene": []
+            }}"#,
+            camera
+        )
+    }
+
+    #[test]
+    fn test_load_sample_job() {
+        let job = Job::from_json(include_str!("../scenes/spheres.json")).unwrap();
+        assert_eq!(job.image.dims(), (1200, 800));
+        assert_eq!(job.settings.samples_per_px, 100);
+        assert_almost_eq(job.camera.camera.aspect_ratio, 1.5);
+
+        let ray = Ray::new(Vector3D::new(0.0, 1.0, 10.0), -Vector3D::unit_z());
+        assert!(job.scene.hit(&ray, 0.0, f32::INFINITY).is_some());
+    }
+
+    #[test]
+    fn test_load_original_job() {
+        // the original sample is a valid description of a degenerate camera
+        let result = Job::from_json(include_str!("../job.json"));
+        assert!(matches!(result, Err(SceneError::DegenerateCamera(_))));
+    }
+
+    #[test]
+    fn test_material_parameters() {
+        let material = serde_json::from_str::<MaterialDesc>(
+            r#"{ "type": "metal", "albedo": [0.1, 0.2, 0.3], "fuzz": 0.4 }"#,
+        )
+        .unwrap()
+        .build(Path::new(""))
+        .unwrap();
+        match material {
+            Material::Metal(albedo, fuzz) => {
+                let color = albedo.value(0.0, 0.0, &Vector3D::zero());
+                assert_vec_eq(&color, &ColorRGB::new(0.1, 0.2, 0.3));
+                assert_almost_eq(fuzz, 0.4);
+            }
+            _ => panic!("expected metal material"),
+        }
+    }
+
+    #[test]
+    fn test_material_textures() {
+        let material = serde_json::from_str::<MaterialDesc>(
+            r#"{ "type": "lambertan", "albedo": { "checker": {
+                "even": [1, 1, 1], "odd": [0, 0, 0], "scale": 2
+            } } }"#,
+        )
+        .unwrap()
+        .build(Path::new(""))
+        .unwrap();
+        match material {
+            Material::Lambertan(albedo) => {
+                let even = albedo.value(0.0, 0.0, &Vector3D::new(1.0, 1.0, 1.0));
+                let odd = albedo.value(0.0, 0.0, &Vector3D::new(3.0, 1.0, 1.0));
+                assert_vec_eq(&even, &ColorRGB::new(1.0, 1.0, 1.0));
+                assert_vec_eq(&odd, &ColorRGB::zero());
+            }
+            _ => panic!("expected lambertan material"),
+        }
+
+        let marble = serde_json::from_str::<MaterialDesc>(
+            r#"{ "type": "metal", "albedo": { "noise": { "seed": 4, "pattern": "marble" } } }"#,
+        )
+        .unwrap()
+        .build(Path::new(""));
+        assert!(matches!(
+            marble,
+            Ok(Material::Metal(
+                Texture::Noise {
+                    pattern: NoisePattern::Marble(7),
+                    ..
+                },
+                _
+            ))
+        ));
+
+        let flat_noise = serde_json::from_str::<MaterialDesc>(
+            r#"{ "type": "metal", "albedo": { "noise": { "seed": 4, "scale": 0 } } }"#,
+        )
+        .unwrap()
+        .build(Path::new(""));
+        assert!(matches!(flat_noise, Err(SceneError::InvalidValue(_))));
+
+        let missing_image = serde_json::from_str::<MaterialDesc>(
+            r#"{ "type": "metal", "albedo": { "image": "nope.png" } }"#,
+        )
+        .unwrap()
+        .build(Path::new(""));
+        assert!(matches!(missing_image, Err(SceneError::Image(..))));
+    }
+
+    #[test]
+    fn test_pbr_material() {
+        let material = serde_json::from_str::<MaterialDesc>(
+            r#"{ "type": "pbr", "base_color": [0.9, 0.6, 0.3], "metallic": 1, "roughness": 0.25 }"#,
+        )
+        .unwrap()
+        .build(Path::new(""))
+        .unwrap();
+        match material {
+            Material::Pbr {
+                base_color,
+                metallic,
+                roughness,
+            } => {
+                let color = base_color.value(0.0, 0.0, &Vector3D::zero());
+                assert_vec_eq(&color, &ColorRGB::new(0.9, 0.6, 0.3));
+                assert_almost_eq(metallic, 1.0);
+                assert_almost_eq(roughness, 0.25);
+            }
+            _ => panic!("expected pbr material"),
+        }
+
+        let invalid = serde_json::from_str::<MaterialDesc>(r#"{ "type": "pbr", "metallic": 2 }"#)
+            .unwrap()
+            .build(Path::new(""));
+        assert!(matches!(invalid, Err(SceneError::InvalidValue(_))));
+    }
+
+    #[test]
+    fn test_unknown_material_field() {
+        for json in [
+            r#"{ "type": "dielectric", "refractive_index": 1.5 }"#,
+            r#"{ "type": "pbr", "base_color": [0.5, 0.5, 0.5], "roughnes": 0.1 }"#,
+        ] {
+            let error = serde_json::from_str::<MaterialDesc>(json).err().unwrap();
+            assert!(error.to_string().contains("unknown field"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_unknown_material() {
+        let result = serde_json::from_str::<MaterialDesc>(r#""plastic""#)
+            .unwrap()
+            .build(Path::new(""));
+        assert!(matches!(result, Err(SceneError::UnknownMaterial(name)) if name == "plastic"));
+    }
+
+    #[test]
+    fn test_material_without_type() {
+        let result = serde_json::from_str::<MaterialDesc>(r#"{ "albedo": [0.1, 0.2, 0.3] }"#)
+            .unwrap()
+            .build(Path::new(""));
+        assert!(matches!(result, Err(SceneError::MissingField(_))));
+    }
+
+    #[test]
+    fn test_missing_field() {
+        let json = r#"{
+            "camera": { "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90 },
+            "image": { "width": 30, "height": 20 },
+            "scene": [ { "sphere": { "center": [0, 0, -1], "material": "lambertan" } } ]
+        }"#;
+        match Job::from_json(json) {
+            Err(SceneError::Parse(e)) => assert!(e.to_string().contains("radius")),
+            _ => panic!("expected parse error"),
+        }
+    }
+
+    #[test]
+    fn test_degenerate_camera() {
+        let cameras = [
+            r#"{ "origin": [1, 1, 1], "lookat": [1, 1, 1], "vup": [0, 1, 0], "vfov": 20 }"#,
+            r#"{ "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 0, 0], "vfov": 20 }"#,
+            r#"{ "origin": [0, 0, 0], "lookat": [0, 2, 0], "vup": [0, 1, 0], "vfov": 20 }"#,
+            r#"{ "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 180 }"#,
+        ];
+        for camera in cameras {
+            let result = Job::from_json(&job_with_camera(camera));
+            assert!(matches!(result, Err(SceneError::DegenerateCamera(_))));
+        }
+    }
+
+    #[test]
+    fn test_mesh_index_out_of_bounds() {
+        let json = r#"{
+            "camera": { "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90 },
+            "image": { "width": 30, "height": 20 },
+            "scene": [ { "mesh": {
+                "vertices": [[0, 0, -1], [1, 0, -1], [0, 1, -1]],
+                "indices": [[0, 1, 3]],
+                "material": "lambertan"
+            } } ]
+        }"#;
+        assert!(matches!(
+            Job::from_json(json),
+            Err(SceneError::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_planar_bodies() {
+        let json = r#"{
+            "camera": { "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90 },
+            "image": { "width": 30, "height": 20 },
+            "scene": [
+                { "plane": { "point": [0, -1, 0], "normal": [0, 1, 0], "material": "lambertan" } },
+                { "xy_rect": { "x": [-1, 1], "y": [-1, 1], "k": -3, "material": "metal" } },
+                { "cuboid": { "min": [-1, -1, -6], "max": [1, 1, -4], "material": "none" } }
+            ]
+        }"#;
+        let job = Job::from_json(json).unwrap();
+        let ray = Ray::new(Vector3D::zero(), -Vector3D::unit_z());
+        let record = job.scene.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert_almost_eq(record.t, 3.0);
+        let ray = Ray::new(Vector3D::zero(), -Vector3D::unit_y());
+        let record = job.scene.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert_almost_eq(record.t, 1.0);
+
+        let zero_normal = json.replace("[0, 1, 0], \"material", "[0, 0, 0], \"material");
+        assert!(matches!(
+            Job::from_json(&zero_normal),
+            Err(SceneError::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_transform_body() {
+        let json = r#"{
+            "camera": { "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90 },
+            "image": { "width": 30, "height": 20 },
+            "scene": [ { "transform": {
+                "bodies": [ { "sphere": { "center": [0, 0, 0], "radius": 1, "material": "none" } } ],
+                "scale": [1, 1, 0.5],
+                "translate": [0, 0, -3]
+            } } ]
+        }"#;
+        let job = Job::from_json(json).unwrap();
+        let ray = Ray::new(Vector3D::zero(), -Vector3D::unit_z());
+        let record = job.scene.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert!((record.t - 2.5).abs() < 1e-5);
+
+        let flat = json.replace("[1, 1, 0.5]", "[1, 0, 1]");
+        assert!(matches!(
+            Job::from_json(&flat),
+            Err(SceneError::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_constant_medium() {
+        let json = r#"{
+            "camera": { "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90 },
+            "image": { "width": 30, "height": 20 },
+            "scene": [ { "constant_medium": {
+                "boundary": { "sphere": { "center": [0, 0, -3], "radius": 1, "material": "none" } },
+                "density": 1000
+            } } ]
+        }"#;
+        let job = Job::from_json(json).unwrap();
+        let ray = Ray::new(Vector3D::zero(), -Vector3D::unit_z());
+        let record = job.scene.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        assert!(matches!(record.material, Material::Isotropic(_)));
+
+        let empty = json.replace("1000", "0");
+        assert!(matches!(
+            Job::from_json(&empty),
+            Err(SceneError::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_missing_obj_model() {
+        let json = r#"{
+            "camera": { "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90 },
+            "image": { "width": 30, "height": 20 },
+            "scene": [ { "obj": { "path": "nope.obj", "scale": 2, "translate": [0, 0, -3] } } ]
+        }"#;
+        assert!(matches!(Job::from_json(json), Err(SceneError::Model(..))));
+
+        // placement is checked before the model is read
+        let flat = json.replace(r#""scale": 2"#, r#""scale": 0"#);
+        assert!(matches!(
+            Job::from_json(&flat),
+            Err(SceneError::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_unknown_body_field() {
+        let json = r#"{
+            "camera": { "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90 },
+            "image": { "width": 30, "height": 20 },
+            "scene": [ { "transform": {
+                "bodies": [ { "sphere": { "center": [0, 0, 0], "radius": 1, "material": "none" } } ],
+                "translate": [0, 0, -3]
+            } } ]
+        }"#;
+        assert!(Job::from_json(json).is_ok());
+        for (from, to) in [
+            (r#""translate""#, r#""translation""#),
+            (r#""radius": 1"#, r#""radius": 1, "time0": 0"#),
+            (
+                r#""material": "none""#,
+                r#""material": { "type": "lambertian", "albedo": { "noise": { "colour": [1, 0, 0] } } }"#,
+            ),
+        ] {
+            let misspelled = json.replace(from, to);
+            let error = Job::from_json(&misspelled).err().unwrap();
+            assert!(matches!(error, SceneError::Parse(_)), "{}", error);
+        }
+
+        let environment = json.replace(
+            r#""scene""#,
+            r#""renderer": { "environment": { "gradient": { "bottom": [0, 0, 0], "top": [1, 1, 1], "mid": [1, 1, 1] } } },
+            "scene""#,
+        );
+        assert!(matches!(
+            Job::from_json(&environment),
+            Err(SceneError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_environment() {
+        let json = r#"{
+            "camera": { "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90 },
+            "image": { "width": 30, "height": 20 },
+            "scene": [],
+            "renderer": { "environment": { "solid": [0.1, 0.2, 0.3] } }
+        }"#;
+        let job = Job::from_json(json).unwrap();
+        let ray = Ray::new(Vector3D::zero(), Vector3D::unit_y());
+        assert_vec_eq(
+            &job.settings.environment.color(&ray),
+            &ColorRGB::new(0.1, 0.2, 0.3),
+        );
+
+        let missing_map = json.replace(
+            r#"{ "solid": [0.1, 0.2, 0.3] }"#,
+            r#"{ "map": "nope.hdr" }"#,
+        );
+        assert!(matches!(
+            Job::from_json(&missing_map),
+            Err(SceneError::Image(..))
+        ));
+    }
+
+    #[test]
+    fn test_emitters_are_lights() {
+        let json = r#"{
+            "camera": { "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90 },
+            "image": { "width": 30, "height": 20 },
+            "scene": [
+                { "sphere": { "center": [0, 3, -3], "radius": 0.5,
+                    "material": { "type": "diffuse_light", "intensity": 4 } } },
+                { "xz_rect": { "x": [-1, 1], "z": [-4, -2], "k": 4, "material": "diffuse_light" } },
+                { "sphere": { "center": [0, 0, -3], "radius": 0.5, "material": "lambertan" } }
+            ],
+            "renderer": { "light_sampling": "mixture" }
+        }"#;
+        let job = Job::from_json(json).unwrap();
+        assert_eq!(job.scene.lights().map(LightList::len), Some(2));
+        // the lights are kept in the accelerated world
+        assert_eq!(job.scene.into_bvh().lights().map(LightList::len), Some(2));
+        assert_eq!(job.settings.light_sampling, LightSampling::Mixture);
+
+        let unknown = json.replace(r#""mixture""#, r#""random""#);
+        assert!(matches!(
+            Job::from_json(&unknown),
+            Err(SceneError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_nested_emitters() {
+        let json = r#"{
+            "camera": { "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90 },
+            "image": { "width": 30, "height": 20 },
+            "scene": [ { "transform": {
+                "bodies": [
+                    { "sphere": { "center": [0, 0, 0], "radius": 1, "material": "diffuse_light" } },
+                    { "sphere": { "center": [3, 0, 0], "radius": 1, "material": "diffuse_light" } }
+                ],
+                "scale": [1, 2, 1],
+                "translate": [0, 0, -5]
+            } } ]
+        }"#;
+        let job = Job::from_json(json).unwrap();
+        let lights = job.scene.lights().unwrap();
+        assert_eq!(lights.len(), 1);
+        // light directions point to the stretched spheres in world space
+        let origin = Vector3D::zero();
+        assert!(lights.pdf(&origin, &Vector3D::new(0.0, 0.3, -1.0).unit()) > 0.0);
+        assert_eq!(lights.pdf(&origin, &Vector3D::unit_z()), 0.0);
+
+        let moving = r#"{
+            "camera": { "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90 },
+            "image": { "width": 30, "height": 20 },
+            "scene": [ { "moving_sphere": { "center0": [0, 0, -3], "center1": [0, 1, -3],
+                "radius": 0.5, "material": "diffuse_light" } } ]
+        }"#;
+        assert!(matches!(
+            Job::from_json(moving),
+            Err(SceneError::InvalidValue(_))
+        ));
+
+        let medium = r#"{
+            "camera": { "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90 },
+            "image": { "width": 30, "height": 20 },
+            "scene": [ { "constant_medium": { "density": 0.5,
+                "boundary": { "sphere": { "center": [0, 0, -3], "radius": 1, "material": "diffuse_light" } } } } ]
+        }"#;
+        assert!(matches!(
+            Job::from_json(medium),
+            Err(SceneError::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_zero_samples() {
+        let json = r#"{
+            "camera": { "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90 },
+            "image": { "width": 30, "height": 20 },
+            "scene": [],
+            "renderer": { "samples_per_px": 0 }
+        }"#;
+        assert!(matches!(
+            Job::from_json(json),
+            Err(SceneError::InvalidValue(_))
+        ));
+        let one = json.replace(r#""samples_per_px": 0"#, r#""samples_per_px": 1"#);
+        assert_eq!(Job::from_json(&one).unwrap().settings.samples_per_px, 1);
+    }
+
+    #[test]
+    fn test_adaptive_sampling() {
+        let json = r#"{
+            "camera": { "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90 },
+            "image": { "width": 30, "height": 20 },
+            "scene": [],
+            "renderer": { "adaptive": { "min_samples": 8, "threshold": 0.05 } }
+        }"#;
+        let job = Job::from_json(json).unwrap();
+        assert_eq!(
+            job.settings.adaptive,
+            Some(AdaptiveSampling {
+                min_samples: 8,
+                max_samples: AdaptiveSampling::default().max_samples,
+                threshold: 0.05,
+            })
+        );
+
+        let fixed = json.replace(r#""adaptive": { "min_samples": 8, "threshold": 0.05 }"#, "");
+        assert_eq!(Job::from_json(&fixed).unwrap().settings.adaptive, None);
+
+        let invalid = json.replace(r#""threshold": 0.05"#, r#""max_samples": 4"#);
+        assert!(matches!(
+            Job::from_json(&invalid),
+            Err(SceneError::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_tone_mapping() {
+        let json = r#"{
+            "camera": { "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90 },
+            "image": { "width": 30, "height": 20 },
+            "scene": [],
+            "renderer": { "tone_mapping": { "exposure": -1.5, "curve": "aces", "gamma": 2.2 } }
+        }"#;
+        let job = Job::from_json(json).unwrap();
+        assert_eq!(
+            job.settings.tone_mapping,
+            ToneMapping {
+                exposure: -1.5,
+                curve: ToneCurve::Aces,
+                transfer: TransferFunction::Gamma(2.2),
+            }
+        );
+
+        let default = json.replace(r#""exposure": -1.5, "curve": "aces", "gamma": 2.2"#, "");
+        let job = Job::from_json(&default).unwrap();
+        assert_eq!(job.settings.tone_mapping, ToneMapping::default());
+
+        let invalid = json.replace("2.2", "0");
+        assert!(matches!(
+            Job::from_json(&invalid),
+            Err(SceneError::InvalidValue(_))
+        ));
+    }
+
+    #[test]
+    fn test_motion_blur() {
+        let camera = r#"{ "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 90,
+            "shutter": [0, 0.5] }"#;
+        let json = job_with_camera(camera).replace(
+            r#""scene": []"#,
+            r#""scene": [ { "moving_sphere": {
+                "center0": [0, 0, -3], "center1": [0, 2, -3], "radius": 0.5, "material": "none"
+            } } ]"#,
+        );
+        let job = Job::from_json(&json).unwrap();
+        assert_eq!(job.camera.shutter, Shutter::new(0.0, 0.5));
+        let ray = Ray::new(Vector3D::zero(), Vector3D::new(0.0, 1.0, -3.0)).with_time(0.5);
+        assert!(job.scene.hit(&ray, 0.0, f32::INFINITY).is_some());
+
+        let reversed = job_with_camera(&camera.replace("[0, 0.5]", "[1, 0.5]"));
+        assert!(matches!(
+            Job::from_json(&reversed),
+            Err(SceneError::DegenerateCamera(_))
+        ));
+    }
+
+    #[test]
+    fn test_camera_focus() {
+        let camera = r#"{ "origin": [0, 0, 0], "lookat": [0, 0, -4], "vup": [0, 1, 0], "vfov": 90,
+            "aperture": 0.1 }"#;
+        let job = Job::from_json(&job_with_camera(camera)).unwrap();
+        assert_almost_eq(job.camera.camera.aperture(), 0.1);
+        assert_almost_eq(job.camera.camera.focus_distance(), 4.0);
+
+        let negative = job_with_camera(&camera.replace("0.1", "-0.1"));
+        assert!(matches!(
+            Job::from_json(&negative),
+            Err(SceneError::DegenerateCamera(_))
+        ));
+        let behind = job_with_camera(&camera.replace("0.1", r#"0.1, "focus_distance": 0"#));
+        assert!(matches!(
+            Job::from_json(&behind),
+            Err(SceneError::DegenerateCamera(_))
+        ));
+    }
+
+    #[test]
+    fn test_aspect_ratio_from_image() {
+        let camera =
+            r#"{ "origin": [0, 0, 0], "lookat": [0, 0, -1], "vup": [0, 1, 0], "vfov": 20 }"#;
+        let job = Job::from_json(&job_with_camera(camera)).unwrap();
+        assert_almost_eq(job.camera.camera.aspect_ratio, 1.5);
+        assert_eq!(
+            job.settings.bounce_depth,
+            RenderSettings::default().bounce_depth
+        );
+    }
+}
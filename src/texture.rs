@@ -0,0 +1,257 @@
+use crate::prelude::*;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Color varying across a surface, evaluated at the surface
+/// (u, v) coordinates or at the hit point in world space
+///
+#[derive(Clone)]
+pub enum Texture {
+    Solid(ColorRGB),
+    /// 3D checker pattern of cubes with the `scale` side
+    /// alternating between two textures
+    Checker {
+        even: Box<Texture>,
+        odd: Box<Texture>,
+        scale: f32,
+    },
+    /// image wrapped around the (u, v) coordinates
+    Image(Arc<ImageTexture>),
+    /// procedural noise pattern in world space scaling the color,
+    /// `scale` is the frequency of the pattern
+    Noise {
+        perlin: Arc<Perlin>,
+        pattern: NoisePattern,
+        scale: f32,
+        color: ColorRGB,
+    },
+}
+
+/// Look of the noise texture
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NoisePattern {
+    /// plain smooth noise
+    Smooth,
+    /// turbulence with the number of octaves
+    Turbulence(u32),
+    /// sine stripes along Z axis distorted by turbulence with the number of octaves
+    Marble(u32),
+}
+
+impl From<ColorRGB> for Texture {
+    fn from(color: ColorRGB) -> Self {
+        Texture::Solid(color)
+    }
+}
+
+impl Texture {
+    pub fn checker(even: impl Into<Texture>, odd: impl Into<Texture>, scale: f32) -> Self {
+        Texture::Checker {
+            even: Box::new(even.into()),
+            odd: Box::new(odd.into()),
+            scale,
+        }
+    }
+
+    /// noise texture with the permutation tables generated from the seed
+    ///
+    pub fn noise(seed: u64, pattern: NoisePattern, scale: f32, color: ColorRGB) -> Self {
+        Texture::Noise {
+            perlin: Arc::new(Perlin::new(seed)),
+            pattern,
+            scale,
+            color,
+        }
+    }
+
+    pub fn value(&self, u: f32, v: f32, point: &Vector3D) -> ColorRGB {
+        match self {
+            Texture::Solid(color) => *color,
+            Texture::Checker { even, odd, scale } => {
+                let cell = |x: f32| (x / scale).floor() as i64;
+                if (cell(point.x) + cell(point.y) + cell(point.z)).rem_euclid(2) == 0 {
+                    even.value(u, v, point)
+                } else {
+                    odd.value(u, v, point)
+                }
+            }
+            Texture::Image(image) => image.sample(u, v),
+            Texture::Noise {
+                perlin,
+                pattern,
+                scale,
+                color,
+            } => {
+                let p = point * *scale;
+                let k = match pattern {
+                    NoisePattern::Smooth => 0.5 * (1.0 + perlin.noise(&p)),
+                    NoisePattern::Turbulence(depth) => perlin.turbulence(&p, *depth),
+                    NoisePattern::Marble(depth) => {
+                        0.5 * (1.0 + (p.z + 10.0 * perlin.turbulence(point, *depth)).sin())
+                    }
+                };
+                *color * k.clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+/// Pixels of an image as linear colors, 8 bit images are decoded
+/// from the sRGB curve and floating point ones are kept
+///
+pub(crate) fn linear_pixels(image: image::DynamicImage) -> (u32, u32, Vec<ColorRGB>) {
+    let is_linear = matches!(
+        image,
+        image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_)
+    );
+    let rgb = image.into_rgb32f();
+    let pixels = rgb
+        .pixels()
+        .map(|p| {
+            let color = ColorRGB::new(p[0], p[1], p[2]);
+            if is_linear {
+                color
+            } else {
+                let srgb = TransferFunction::Srgb;
+                ColorRGB::new(
+                    srgb.decode(color.x),
+                    srgb.decode(color.y),
+                    srgb.decode(color.z),
+                )
+            }
+        })
+        .collect();
+    (rgb.width(), rgb.height(), pixels)
+}
+
+/// Image mapped onto the unit (u, v) square with v going up,
+/// coordinates outside of the square wrap around
+///
+pub struct ImageTexture {
+    width: u32,
+    height: u32,
+    pixels: Vec<ColorRGB>,
+}
+
+impl ImageTexture {
+    /// load any image format supported by the `image` crate
+    ///
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, image::ImageError> {
+        Ok(Self::from_image(image::open(path)?))
+    }
+
+    pub fn from_image(image: image::DynamicImage) -> Self {
+        let (width, height, pixels) = linear_pixels(image);
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn dims(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// color of the pixel under the (u, v) coordinates
+    ///
+    pub fn sample(&self, u: f32, v: f32) -> ColorRGB {
+        let u = u.rem_euclid(1.0);
+        let v = 1.0 - v.rem_euclid(1.0);
+        let i = ((u * self.width as f32) as u32).min(self.width - 1);
+        let j = ((v * self.height as f32) as u32).min(self.height - 1);
+        self.pixels[(j * self.width + i) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checker_texture() {
+        let white = ColorRGB::new(1.0, 1.0, 1.0);
+        let texture = Texture::checker(white, ColorRGB::zero(), 0.5);
+        assert_vec_eq(
+            &texture.value(0.0, 0.0, &Vector3D::new(0.1, 0.1, 0.1)),
+            &white,
+        );
+        assert_vec_eq(
+            &texture.value(0.0, 0.0, &Vector3D::new(0.6, 0.1, 0.1)),
+            &ColorRGB::zero(),
+        );
+        assert_vec_eq(
+            &texture.value(0.0, 0.0, &Vector3D::new(-0.1, 0.1, 0.1)),
+            &ColorRGB::zero(),
+        );
+        assert_vec_eq(
+            &texture.value(0.0, 0.0, &Vector3D::new(-0.1, -0.1, 0.1)),
+            &white,
+        );
+    }
+
+    #[test]
+    fn test_noise_textures() {
+        let color = ColorRGB::new(1.0, 0.5, 0.25);
+        let p = Vector3D::new(0.3, 1.7, -2.2);
+        for pattern in [
+            NoisePattern::Smooth,
+            NoisePattern::Turbulence(7),
+            NoisePattern::Marble(7),
+        ] {
+            let texture = Texture::noise(3, pattern, 4.0, color);
+            let value = texture.value(0.0, 0.0, &p);
+            assert!(value.x >= 0.0 && value.x <= 1.0);
+            assert_almost_eq(value.y, 0.5 * value.x);
+            assert_vec_eq(
+                &value,
+                &Texture::noise(3, pattern, 4.0, color).value(0.0, 0.0, &p),
+            );
+        }
+
+        // smooth noise is gray at the lattice points
+        let texture = Texture::noise(3, NoisePattern::Smooth, 1.0, color);
+        assert_vec_eq(
+            &texture.value(0.0, 0.0, &Vector3D::new(1.0, 2.0, 3.0)),
+            &(color * 0.5),
+        );
+    }
+
+    #[test]
+    fn test_image_texture() {
+        // left column red, right column green, top row brighter
+        let image = image::Rgb32FImage::from_fn(2, 2, |i, j| {
+            let k = if j == 0 { 1.0 } else { 0.5 };
+            if i == 0 {
+                image::Rgb([k, 0.0, 0.0])
+            } else {
+                image::Rgb([0.0, k, 0.0])
+            }
+        });
+        let texture = ImageTexture::from_image(image::DynamicImage::ImageRgb32F(image));
+        assert_eq!(texture.dims(), (2, 2));
+        assert_vec_eq(&texture.sample(0.25, 0.75), &ColorRGB::new(1.0, 0.0, 0.0));
+        assert_vec_eq(&texture.sample(0.75, 0.25), &ColorRGB::new(0.0, 0.5, 0.0));
+        assert_vec_eq(&texture.sample(1.0, 1.0), &ColorRGB::new(0.5, 0.0, 0.0));
+        assert_vec_eq(&texture.sample(-0.25, 0.25), &ColorRGB::new(0.0, 0.5, 0.0));
+    }
+
+    #[test]
+    fn test_srgb_texture_round_trip() {
+        let image = image::RgbImage::from_fn(256, 1, |i, _| image::Rgb([i as u8, 188, 0]));
+        let texture = ImageTexture::from_image(image::DynamicImage::ImageRgb8(image));
+        // sRGB 188 is the middle gray
+        assert!((texture.sample(0.0, 0.5).y - 0.5).abs() < 5e-3);
+
+        // rendering the decoded texture with the default display transform gives it back
+        let mut rendered = Image::new(256, 1);
+        for i in 0..256 {
+            rendered.set_at(i, 0, texture.sample((i as f32 + 0.5) / 256.0, 0.5));
+        }
+        let bytes = rendered.as_bytes();
+        for i in 0..256 {
+            assert_eq!(&bytes[3 * i..3 * i + 3], &[i as u8, 188, 0]);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use crate::prelude::*;
+use std::fmt;
+
+/// Curve compressing linear radiance into the displayable 0 to 1 range
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ToneCurve {
+    /// radiance above 1.0 is clipped
+    #[default]
+    Clamp,
+    /// x / (1 + x), never reaches white
+    Reinhard,
+    /// John Hable's filmic curve from Uncharted 2 with a toe and a shoulder
+    /// http://filmicworlds.com/blog/filmic-tonemapping-operators/
+    Filmic,
+    /// Krzysztof Narkowicz's fit of the ACES reference rendering transform
+    /// https://knarkowicz.wordpress.com/2016/01/06/aces-filmic-tone-mapping-curve/
+    Aces,
+}
+
+impl ToneCurve {
+    fn apply(&self, x: f32) -> f32 {
+        match self {
+            ToneCurve::Clamp => x,
+            ToneCurve::Reinhard => x / (1.0 + x),
+            ToneCurve::Filmic => {
+                // exposure bias and linear white point of the original curve
+                const WHITE: f32 = 11.2;
+                hable(2.0 * x) / hable(WHITE)
+            }
+            ToneCurve::Aces => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+        }
+    }
+}
+
+fn hable(x: f32) -> f32 {
+    let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+    (x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f) - e / f
+}
+
+/// Encoding of the tone mapped values for the display
+///
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum TransferFunction {
+    /// piecewise sRGB curve expected by most displays and image viewers
+    /// https://en.wikipedia.org/wiki/SRGB#Transfer_function_(%22gamma%22)
+    #[default]
+    Srgb,
+    /// plain power 1 / gamma
+    Gamma(f32),
+}
+
+impl TransferFunction {
+    fn apply(&self, x: f32) -> f32 {
+        match self {
+            TransferFunction::Srgb if x <= 0.003_130_8 => 12.92 * x,
+            TransferFunction::Srgb => 1.055 * x.powf(1.0 / 2.4) - 0.055,
+            TransferFunction::Gamma(gamma) => x.powf(1.0 / gamma),
+        }
+    }
+
+    /// linear value of the encoded 0 to 1 value, inverse of the encoding
+    /// used for the display e.g. to read 8 bit textures
+    ///
+    pub fn decode(&self, x: f32) -> f32 {
+        match self {
+            TransferFunction::Srgb if x <= 0.040_45 => x / 12.92,
+            TransferFunction::Srgb => ((x + 0.055) / 1.055).powf(2.4),
+            TransferFunction::Gamma(gamma) => x.powf(*gamma),
+        }
+    }
+}
+
+/// Display transform from the linear radiance of the image to the 0 to 1
+/// values of the 8 bit formats, float formats keep the radiance untouched
+///
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct ToneMapping {
+    /// exposure adjustment in stops, every stop doubles the radiance
+    pub exposure: f32,
+    pub curve: ToneCurve,
+    pub transfer: TransferFunction,
+}
+
+/// Invalid tone mapping settings
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToneMappingError {
+    /// exposure is not a finite number of stops
+    Exposure(f32),
+    /// gamma of the power transfer is not positive and finite
+    Gamma(f32),
+}
+
+impl fmt::Display for ToneMappingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToneMappingError::Exposure(exposure) => {
+                write!(f, "tone mapping exposure {} is not finite", exposure)
+            }
+            ToneMappingError::Gamma(gamma) => {
+                write!(f, "tone mapping gamma {} is not positive and finite", gamma)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ToneMappingError {}
+
+impl ToneMapping {
+    /// error if the exposure is not finite or the gamma
+    /// is not positive and finite
+    ///
+    pub fn validate(&self) -> Result<(), ToneMappingError> {
+        if !self.exposure.is_finite() {
+            return Err(ToneMappingError::Exposure(self.exposure));
+        }
+        match self.transfer {
+            TransferFunction::Gamma(gamma) if !(gamma > 0.0 && gamma.is_finite()) => {
+                Err(ToneMappingError::Gamma(gamma))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// display value of the linear radiance, always in range 0 to 1
+    ///
+    pub fn apply(&self, color: ColorRGB) -> ColorRGB {
+        let scale = self.exposure.exp2();
+        let map = |x: f32| {
+            // max also turns NaN into zero
+            let x = self.curve.apply((x * scale).max(0.0));
+            self.transfer.apply(x.clamp(0.0, 1.0))
+        };
+        ColorRGB::new(map(color.x), map(color.y), map(color.z))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curves_are_monotonic() {
+        for curve in [
+            ToneCurve::Clamp,
+            ToneCurve::Reinhard,
+            ToneCurve::Filmic,
+            ToneCurve::Aces,
+        ] {
+            assert!(curve.apply(0.0).abs() < 1e-3);
+            let mut previous = curve.apply(0.0);
+            for i in 1..100 {
+                let value = curve.apply(i as f32 * 0.1);
+                assert!(value > previous, "{:?} at {}", curve, i);
+                previous = value;
+            }
+        }
+        // Reinhard keeps bright radiance below white, the filmic curves saturate
+        assert!(ToneCurve::Reinhard.apply(100.0) < 1.0);
+        assert_almost_eq(ToneCurve::Filmic.apply(5.6), 1.0);
+        for curve in [ToneCurve::Clamp, ToneCurve::Filmic, ToneCurve::Aces] {
+            let tone_mapping = ToneMapping {
+                curve,
+                transfer: TransferFunction::Gamma(1.0),
+                ..Default::default()
+            };
+            let white = tone_mapping.apply(ColorRGB::new(100.0, 100.0, 100.0));
+            assert_vec_eq(&white, &ColorRGB::new(1.0, 1.0, 1.0));
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ToneMapping::default().validate().is_ok());
+        for exposure in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let tone_mapping = ToneMapping {
+                exposure,
+                ..Default::default()
+            };
+            assert!(matches!(
+                tone_mapping.validate(),
+                Err(ToneMappingError::Exposure(_))
+            ));
+        }
+        for gamma in [0.0, -2.2, f32::NAN, f32::INFINITY] {
+            let tone_mapping = ToneMapping {
+                transfer: TransferFunction::Gamma(gamma),
+                ..Default::default()
+            };
+            assert!(matches!(
+                tone_mapping.validate(),
+                Err(ToneMappingError::Gamma(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_srgb_transfer() {
+        let srgb = TransferFunction::Srgb;
+        assert_almost_eq(srgb.apply(0.0), 0.0);
+        assert_almost_eq(srgb.apply(1.0), 1.0);
+        assert!((srgb.apply(0.5) - 0.735_4).abs() < 1e-4);
+        // linear segment near black
+        assert_almost_eq(srgb.apply(0.001), 0.012_92);
+
+        for transfer in [srgb, TransferFunction::Gamma(2.2)] {
+            for x in [0.0, 0.002, 0.04, 0.2, 0.5, 1.0] {
+                assert!((transfer.decode(transfer.apply(x)) - x).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_exposure_and_gamma() {
+        let tone_mapping = ToneMapping {
+            exposure: 1.0,
+            transfer: TransferFunction::Gamma(2.0),
+            ..Default::default()
+        };
+        // one stop up doubles 0.125 to 0.25, its square root is 0.5
+        let color = tone_mapping.apply(ColorRGB::new(0.125, 4.0, -1.0));
+        assert_vec_eq(&color, &ColorRGB::new(0.5, 1.0, 0.0));
+
+        let nan = ToneMapping::default().apply(ColorRGB::new(f32::NAN, 0.0, 0.0));
+        assert_almost_eq(nan.x, 0.0);
+    }
+}